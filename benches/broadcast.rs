@@ -0,0 +1,116 @@
+//! How long it takes to broadcast to a lot of clients
+//!
+//! `cargo bench --bench broadcast -- 10000 100` will open 10000 connections and broadcast 100
+//! messages to them, every connection uses two sockets so make sure `ulimit -n` is high enough
+
+use std::env;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use razer::event::handler::EventHandler;
+use razer::listener::Listener;
+use razer::protocol::ServerMessage;
+use razer::send::broadcast_message;
+use razer_ws::{Builder, Handler, Message, Settings};
+
+#[derive(Clone)]
+struct Server;
+
+impl EventHandler for Server {}
+
+struct Client {
+    received: Arc<AtomicUsize>,
+}
+
+impl Handler for Client {
+    // the server also sends the events it wants when the connection opens, only the broadcasts
+    // are counted
+    fn on_message(&mut self, msg: Message) -> razer_ws::Result<()> {
+        let text = msg.as_text()?;
+        if let Ok(ServerMessage::Js { .. }) = ServerMessage::decode(text) {
+            self.received.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+}
+
+fn wait_for(what: &str, done: impl Fn() -> bool) {
+    let start = Instant::now();
+    while !done() {
+        if start.elapsed() > Duration::from_secs(120) {
+            panic!("timed out waiting for {}", what);
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+fn main() {
+    // cargo passes --bench to every bench, so only the numbers are read
+    let mut numbers = env::args().skip(1).filter_map(|arg| arg.parse().ok());
+    let connections: usize = numbers.next().unwrap_or(10_000);
+    let rounds: usize = numbers.next().unwrap_or(100);
+    let settings = Settings {
+        max_connections: connections,
+        queue_size: 16,
+        ..Settings::default()
+    };
+
+    let server = Listener::new()
+        .address("127.0.0.1")
+        .port(0)
+        .settings(settings)
+        .spawn(Server)
+        .unwrap();
+    let url = format!("ws://{}", server.local_addr());
+    let broadcaster = server.broadcaster();
+
+    let received = Arc::new(AtomicUsize::new(0));
+    let counter = received.clone();
+    let mut clients = Builder::new()
+        .with_settings(settings)
+        .build(move |_out| Client {
+            received: counter.clone(),
+        })
+        .unwrap();
+    let url = url::Url::parse(&url).unwrap();
+    for _ in 0..connections {
+        clients.connect(url.clone()).unwrap();
+    }
+    let client_sender = clients.broadcaster();
+    thread::spawn(move || clients.run().unwrap());
+
+    let start = Instant::now();
+    wait_for("the connections", || server.connections() == connections);
+    println!(
+        "opened {} connections in {:?}",
+        connections,
+        start.elapsed()
+    );
+
+    let message = ServerMessage::Js {
+        code: "document.title = \"a message that every client gets\"".to_string(),
+    };
+    let start = Instant::now();
+    for _ in 0..rounds {
+        broadcast_message(&broadcaster, &message).unwrap();
+    }
+    let sent = start.elapsed();
+    wait_for("the messages", || {
+        received.load(Ordering::Relaxed) == connections * rounds
+    });
+    let elapsed = start.elapsed();
+    println!(
+        "{} broadcasts took {:?} to send and {:?} to arrive",
+        rounds, sent, elapsed
+    );
+    println!(
+        "{:?} per broadcast, {:.0} messages per second",
+        elapsed / rounds as u32,
+        (connections * rounds) as f64 / elapsed.as_secs_f64()
+    );
+
+    client_sender.shutdown().unwrap();
+    server.shutdown().unwrap();
+}
@@ -17,6 +17,7 @@ use std::fmt;
 #[derive(Debug, Clone)]
 pub enum Signal {
     Message(message::Message),
+    Multicast(Vec<(Token, u32)>, message::Message),
     Close(CloseCode, Cow<'static, str>),
     Ping(Vec<u8>),
     Pong(Vec<u8>),
@@ -154,6 +155,26 @@ impl Sender {
             .map_err(Error::from)
     }
 
+    /// Send a message to some of the connections.
+    ///
+    /// The targets are the token and connection_id of every connection that should get a copy of
+    /// the message. All of them are sent from the event loop with one command, so this is much
+    /// cheaper than calling `send` on a Sender for each of them. Connections that are gone or
+    /// whose token was given to a new connection are skipped.
+    #[inline]
+    pub fn multicast<M>(&self, targets: Vec<(Token, u32)>, msg: M) -> Result<()>
+    where
+        M: Into<message::Message>,
+    {
+        self.channel
+            .send(Command {
+                token: ALL,
+                signal: Signal::Multicast(targets, msg.into()),
+                connection_id: self.connection_id,
+            })
+            .map_err(Error::from)
+    }
+
     /// Send a close code to the other endpoint.
     #[inline]
     pub fn close(&self, code: CloseCode) -> Result<()> {
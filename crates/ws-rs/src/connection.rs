@@ -1,4 +1,5 @@
 use std::borrow::Borrow;
+use std::cmp;
 use std::collections::VecDeque;
 use std::io::{Cursor, Read, Seek, SeekFrom, Write};
 use std::mem::replace;
@@ -583,9 +584,10 @@ where
                             self.events = Ready::empty();
                             return Ok(());
                         }
-                        if let Some(ref request) = Request::parse(req.get_ref())? {
+                        if let Some(mut request) = Request::parse(req.get_ref())? {
                             trace!("Handshake request received: \n{}", request);
-                            let response = self.handler.on_request(request)?;
+                            request.set_peer_addr(self.socket.peer_addr().ok());
+                            let response = self.handler.on_request(&request)?;
                             response.format(res.get_mut())?;
                             self.events.remove(Ready::readable());
                             self.events.insert(Ready::writable());
@@ -717,7 +719,10 @@ where
     }
 
     fn read_frames(&mut self) -> Result<()> {
-        let max_size = self.settings.max_fragment_size as u64;
+        let max_size = cmp::min(
+            self.settings.max_fragment_size,
+            self.settings.max_message_size,
+        ) as u64;
         while let Some(mut frame) = Frame::parse(&mut self.in_buffer, max_size)? {
             match self.state {
                 // Ignore data received after receiving close frame
@@ -894,6 +899,12 @@ where
                                     first.payload().len() + frame.payload().len(),
                                     |len, frame| len + frame.payload().len(),
                                 );
+                                if size > self.settings.max_message_size {
+                                    return Err(Error::new(
+                                        Kind::Capacity,
+                                        "Exceeded max message size.",
+                                    ));
+                                }
                                 match first.opcode() {
                                     OpCode::Text => {
                                         trace!("Constructing text message from fragments: {:?} -> {:?} -> {:?}", first, self.fragments.iter().collect::<Vec<&Frame>>(), frame);
@@ -954,7 +965,13 @@ where
                         ));
                     } else {
                         trace!("Received non-final fragment frame {:?}", frame);
-                        if !self.settings.fragments_grow
+                        let size = self.fragments.iter().fold(
+                            frame.payload().len(),
+                            |len, frame| len + frame.payload().len(),
+                        );
+                        if size > self.settings.max_message_size {
+                            return Err(Error::new(Kind::Capacity, "Exceeded max message size."));
+                        } else if !self.settings.fragments_grow
                             && self.settings.fragments_capacity == self.fragments.len()
                         {
                             return Err(Error::new(Kind::Capacity, "Exceeded max fragments."));
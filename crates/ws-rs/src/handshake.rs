@@ -115,9 +115,24 @@ pub struct Request {
     path: String,
     method: String,
     headers: Vec<(String, Vec<u8>)>,
+    peer_addr: Option<SocketAddr>,
 }
 
 impl Request {
+    /// The address of the socket that sent this request, this is `None` for requests that were
+    /// not read from a socket.
+    ///
+    /// Unlike `client_addr` this does not look at any headers, so it can not be made up by the
+    /// client, but it will be the address of the proxy if there is one.
+    pub fn peer_addr(&self) -> Option<SocketAddr> {
+        self.peer_addr
+    }
+
+    /// Set the address of the socket that sent this request.
+    pub fn set_peer_addr(&mut self, peer_addr: Option<SocketAddr>) {
+        self.peer_addr = peer_addr
+    }
+
     /// Get the value of the first instance of an HTTP header.
     pub fn header(&self, header: &str) -> Option<&Vec<u8>> {
         self.headers
@@ -337,6 +352,7 @@ impl Request {
                     .iter()
                     .map(|h| (h.name.into(), h.value.into()))
                     .collect(),
+                peer_addr: None,
             }))
         } else {
             Ok(None)
@@ -378,6 +394,7 @@ impl Request {
             path: format!("{}{}", url.path(), query),
             method: "GET".to_owned(),
             headers: headers,
+            peer_addr: None,
         };
 
         debug!("Built request from URL:\n{}", req);
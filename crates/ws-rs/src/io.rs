@@ -8,6 +8,7 @@ use mio;
 use mio::tcp::{TcpListener, TcpStream};
 use mio::{Poll, PollOpt, Ready, Token};
 use mio_extras;
+use net2::TcpBuilder;
 
 use url::Url;
 
@@ -18,6 +19,7 @@ use super::Settings;
 use communication::{Command, Sender, Signal};
 use connection::Connection;
 use factory::Factory;
+use message::Message;
 use slab::Slab;
 use result::{Error, Kind, Result};
 
@@ -26,6 +28,8 @@ const QUEUE: Token = Token(usize::MAX - 3);
 const TIMER: Token = Token(usize::MAX - 4);
 pub const ALL: Token = Token(usize::MAX - 5);
 const SYSTEM: Token = Token(usize::MAX - 6);
+// listeners count down from here, connections count up from zero so they never meet
+const LISTENER: usize = usize::MAX - 7;
 
 type Conn<F> = Connection<<F as Factory>::Handler>;
 
@@ -82,7 +86,7 @@ pub struct Handler<F>
 where
     F: Factory,
 {
-    listener: Option<TcpListener>,
+    listeners: Vec<TcpListener>,
     connections: Slab<Conn<F>>,
     factory: F,
     settings: Settings,
@@ -105,7 +109,7 @@ where
             .capacity(TIMER_CAPACITY)
             .build();
         Handler {
-            listener: None,
+            listeners: Vec::new(),
             connections: Slab::with_capacity(settings.max_connections),
             factory,
             settings,
@@ -122,26 +126,42 @@ where
     }
 
     pub fn listen(&mut self, poll: &mut Poll, addr: &SocketAddr) -> Result<&mut Handler<F>> {
-        debug_assert!(
-            self.listener.is_none(),
-            "Attempted to listen for connections from two addresses on the same websocket."
-        );
-
-        let tcp = TcpListener::bind(addr)?;
-        // TODO: consider net2 in order to set reuse_addr
-        poll.register(&tcp, ALL, Ready::readable(), PollOpt::level())?;
-        self.listener = Some(tcp);
+        let builder = match *addr {
+            SocketAddr::V4(_) => TcpBuilder::new_v4()?,
+            SocketAddr::V6(_) => {
+                let builder = TcpBuilder::new_v6()?;
+                // so the same port can also be bound on an ipv4 address
+                builder.only_v6(true)?;
+                builder
+            }
+        };
+        #[cfg(unix)]
+        builder.reuse_address(true)?;
+        let tcp = TcpListener::from_std(builder.bind(addr)?.listen(1024)?)?;
+        let token = Token(LISTENER - self.listeners.len());
+        poll.register(&tcp, token, Ready::readable(), PollOpt::level())?;
+        self.listeners.push(tcp);
         Ok(self)
     }
 
     pub fn local_addr(&self) -> ::std::io::Result<SocketAddr> {
-        if let Some(ref listener) = self.listener {
+        if let Some(listener) = self.listeners.first() {
             listener.local_addr()
         } else {
             Err(IoError::new(ErrorKind::NotFound, "Not a listening socket"))
         }
     }
 
+    pub fn local_addrs(&self) -> ::std::io::Result<Vec<SocketAddr>> {
+        self.listeners.iter().map(|listener| listener.local_addr()).collect()
+    }
+
+    fn listener(&self, token: Token) -> Option<&TcpListener> {
+        LISTENER
+            .checked_sub(token.0)
+            .and_then(|index| self.listeners.get(index))
+    }
+
     #[cfg(any(feature = "ssl", feature = "nativetls"))]
     pub fn connect(&mut self, poll: &mut Poll, url: Url) -> Result<()> {
         let settings = self.settings;
@@ -580,7 +600,7 @@ where
 
     #[inline]
     fn is_client(&self) -> bool {
-        self.listener.is_none()
+        self.listeners.is_empty()
     }
 
     #[inline]
@@ -603,10 +623,9 @@ where
                 debug_assert!(false, "System token used for io event. This is a bug!");
                 error!("System token used for io event. This is a bug!");
             }
-            ALL => {
+            token if self.listener(token).is_some() => {
                 if events.is_readable() {
-                    match self.listener
-                        .as_ref()
+                    match self.listener(token)
                         .expect("No listener provided for server websocket connections")
                         .accept()
                     {
@@ -750,6 +769,10 @@ where
                             }
                         }
                     }
+                    Signal::Multicast(targets, msg) => {
+                        self.multicast(poll, targets, msg);
+                        return;
+                    }
                     Signal::Close(code, reason) => {
                         trace!("Broadcasting close: {:?} - {}", code, reason);
                         for (_, conn) in self.connections.iter_mut() {
@@ -836,6 +859,10 @@ where
                             )
                         }
                     }
+                    Signal::Multicast(targets, msg) => {
+                        self.multicast(poll, targets, msg);
+                        return;
+                    }
                     Signal::Close(code, reason) => {
                         if let Some(conn) = self.connections.get_mut(token.into()) {
                             if conn.connection_id() == connection_id {
@@ -924,6 +951,28 @@ where
         }
     }
 
+    fn multicast(&mut self, poll: &mut Poll, targets: Vec<(Token, u32)>, msg: Message) {
+        trace!("Multicasting message to {} connections: {:?}", targets.len(), msg);
+        for (token, connection_id) in targets {
+            if let Some(conn) = self.connections.get_mut(token.into()) {
+                if conn.connection_id() != connection_id {
+                    trace!("Connection disconnected while a multicast was waiting in the queue.");
+                    continue;
+                }
+                if let Err(err) = conn.send_message(msg.clone()) {
+                    conn.error(err);
+                    continue;
+                }
+            } else {
+                trace!("Connection disconnected while a multicast was waiting in the queue.");
+                continue;
+            }
+            if let Err(err) = self.schedule(poll, &self.connections[token.into()]) {
+                self.connections[token.into()].error(err)
+            }
+        }
+    }
+
     fn handle_timeout(&mut self, poll: &mut Poll, Timeout { connection, event }: Timeout) {
         let active = {
             if let Some(conn) = self.connections.get_mut(connection.into()) {
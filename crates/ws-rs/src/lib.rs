@@ -8,6 +8,7 @@ extern crate bytes;
 extern crate httparse;
 extern crate mio;
 extern crate mio_extras;
+extern crate net2;
 #[cfg(feature = "ssl")]
 extern crate openssl;
 #[cfg(feature = "nativetls")]
@@ -161,6 +162,10 @@ pub struct Settings {
     /// The maximum length of acceptable incoming frames. Messages longer than this will be rejected.
     /// Default: unlimited
     pub max_fragment_size: usize,
+    /// The maximum length of acceptable incoming messages, counting every fragment of the
+    /// message. Messages longer than this will be rejected.
+    /// Default: unlimited
+    pub max_message_size: usize,
     /// The size of the incoming buffer. A larger buffer uses more memory but will allow for fewer
     /// reallocations.
     /// Default: 2048
@@ -249,6 +254,7 @@ impl Default for Settings {
             fragments_grow: true,
             fragment_size: u16::max_value() as usize,
             max_fragment_size: usize::max_value(),
+            max_message_size: usize::max_value(),
             in_buffer_capacity: 2048,
             in_buffer_grow: true,
             out_buffer_capacity: 2048,
@@ -292,6 +298,9 @@ where
     /// If the `addr_spec` yields multiple addresses this will return after the
     /// first successful bind. `local_addr` can be called to determine which
     /// address it ended up binding to.
+    /// Calling `bind` again listens on another address as well, so one server can accept
+    /// connections on both an IPv4 and an IPv6 address. IPv6 addresses only accept IPv6
+    /// connections.
     /// After the server is successfully bound you should start it using `run`.
     pub fn bind<A>(mut self, addr_spec: A) -> Result<WebSocket<F>>
     where
@@ -356,6 +365,11 @@ where
     pub fn local_addr(&self) -> ::std::io::Result<SocketAddr> {
         self.handler.local_addr()
     }
+
+    /// Get every local socket address this socket is bound to, in the order they were bound.
+    pub fn local_addrs(&self) -> ::std::io::Result<Vec<SocketAddr>> {
+        self.handler.local_addrs()
+    }
 }
 
 /// Utility for constructing a WebSocket from various settings.
@@ -1,13 +1,14 @@
 use std::sync::Mutex;
+use std::time::Duration;
 
 use lazy_static::lazy_static;
 
-use razer::event::event_type::Event::{EVAL, JS};
+use razer::dom::{apply, broadcast, escape_html, Command, Position};
+use razer::eval::eval;
 use razer::event::handler::EventHandler;
+use razer::event::types::{GenericEvent, KeyboardEvent};
 use razer::listener::Listener;
-use razer::send::{broadcast, send};
 use razer::Sender;
-use razer::Value;
 
 lazy_static! {
     static ref MESSAGES: Mutex<Vec<String>> = Mutex::new(Vec::new());
@@ -23,41 +24,45 @@ impl Clone for Handler {
 }
 
 impl EventHandler for Handler {
-    fn load(&self, _event: Value, ctx: &Sender) {
-        send(
-            ctx,
-            JS,
-            &format!(
-                "document.getElementById(\"text\").innerHTML += \"<p>{}</p>\"",
-                MESSAGES.lock().unwrap().join("</p><p>")
-            ),
-        )
-            .unwrap();
+    fn load(&mut self, _event: GenericEvent, ctx: &Sender) {
+        let messages: String = MESSAGES
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|message| format!("<p>{}</p>", escape_html(message)))
+            .collect();
+        apply(ctx, vec![Command::set_html("#text", &messages)]).unwrap();
     }
 
-    fn keydown(&self, event: Value, ctx: &Sender) {
-        if event["key"].as_str().unwrap() == "Enter" && event["srcElement"]["id"] == "input" {
-            send(ctx, EVAL, "document.getElementById(\"input\").value").unwrap();
-            send(ctx, JS, "document.getElementById(\"input\").value = \"\"").unwrap();
+    fn keydown(&mut self, event: KeyboardEvent, ctx: &Sender) {
+        if event.key == "Enter" && event.target.id == "input" {
+            let message: String =
+                match eval(ctx, "document.getElementById(\"input\").value")
+                    .wait_as(Duration::from_secs(5))
+                {
+                    Ok(message) => message,
+                    Err(err) => {
+                        println!("could not read the message: {}", err);
+                        return;
+                    }
+                };
+            apply(ctx, vec![Command::set_value("#input", "")]).unwrap();
+            MESSAGES.lock().unwrap().push(message.clone());
+            broadcast(
+                ctx,
+                vec![Command::insert(
+                    "#text",
+                    Position::BeforeEnd,
+                    &format!("<p>{}</p>", escape_html(&message)),
+                )],
+            )
+            .unwrap();
         }
     }
-
-    fn eval(&self, event: Value, ctx: &Sender) {
-        MESSAGES
-            .lock()
-            .unwrap()
-            .push(event["event"].as_str().unwrap().to_string());
-        broadcast(
-            ctx,
-            JS,
-            &format!(
-                "document.getElementById(\"text\").innerHTML += \"<p>{}</p>\"",
-                event["event"].as_str().unwrap().replace("\"", "\\\\\"")
-            ),
-        );
-    }
 }
 
-fn main() {
-    Listener::new().start(Handler);
+fn main() -> razer::Result<()> {
+    Listener::new()
+        .index(include_str!("chat.html"))
+        .start(Handler)
 }
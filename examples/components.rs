@@ -0,0 +1,75 @@
+use razer::component::{Component, Html, Page};
+use razer::event::types::{InputEvent, MouseEvent};
+use razer::listener::Listener;
+use razer::session::from_fn;
+use razer::vdom::Node;
+use razer::Sender;
+
+pub struct App {
+    name: String,
+}
+
+impl Component for App {
+    fn render(&self) -> Html {
+        Node::element("main")
+            .child(
+                Node::element("input")
+                    .attr("id", "name")
+                    .attr("placeholder", "your name"),
+            )
+            .child(Node::element("h1").child(Node::text(&format!("hello {}", self.name))))
+            .child(Node::component("apples"))
+            .child(Node::component("pears"))
+    }
+
+    fn input(&mut self, event: InputEvent, _ctx: &Sender) -> bool {
+        if event.target.id != "name" {
+            return false;
+        }
+        self.name = event.target.value.unwrap_or_default();
+        true
+    }
+}
+
+// every counter only sends its own part of the page when it is clicked
+pub struct Counter {
+    fruit: &'static str,
+    count: u32,
+}
+
+impl Counter {
+    fn new(fruit: &'static str) -> Counter {
+        Counter { fruit, count: 0 }
+    }
+}
+
+impl Component for Counter {
+    fn render(&self) -> Html {
+        Node::element("p")
+            .child(Node::text(&format!("{} {} ", self.count, self.fruit)))
+            .child(Node::element("button").child(Node::text("add one")))
+    }
+
+    fn click(&mut self, event: MouseEvent, _ctx: &Sender) -> bool {
+        if event.target.tag != "button" {
+            return false;
+        }
+        self.count += 1;
+        true
+    }
+}
+
+fn main() -> razer::Result<()> {
+    Listener::new()
+        .index(include_str!("components.html"))
+        .start(from_fn(|| {
+            Page::new(
+                "#app",
+                App {
+                    name: String::new(),
+                },
+            )
+            .with("apples", Counter::new("apples"))
+            .with("pears", Counter::new("pears"))
+        }))
+}
@@ -4,10 +4,10 @@ use lazy_static::lazy_static;
 
 use razer::event::event_type::Event::JS;
 use razer::event::handler::EventHandler;
+use razer::event::types::{GenericEvent, MouseEvent};
 use razer::listener::Listener;
 use razer::send::{broadcast, send};
 use razer::Sender;
-use razer::Value;
 
 lazy_static! {
     static ref DATA: Mutex<u32> = Mutex::new(0);
@@ -23,9 +23,9 @@ impl Clone for Handler {
 }
 
 impl EventHandler for Handler {
-    fn load(&self, _event: Value, ctx: &Sender) {
+    fn load(&mut self, _event: GenericEvent, ctx: &Sender) {
         send(
-            &ctx,
+            ctx,
             JS,
             format!(
                 "document.getElementById(\"counter\").innerHTML = {}",
@@ -36,21 +36,24 @@ impl EventHandler for Handler {
             .unwrap();
     }
 
-    fn click(&self, _event: Value, ctx: &Sender) {
+    fn click(&mut self, _event: MouseEvent, ctx: &Sender) {
         let mut counter = DATA.lock().unwrap();
         *counter += 1;
         broadcast(
-            &ctx,
+            ctx,
             JS,
             format!(
                 "document.getElementById(\"counter\").innerHTML = {}",
                 counter
             )
                 .as_str(),
-        );
+        )
+        .unwrap();
     }
 }
 
-fn main() {
-    Listener::new().start(Handler);
+fn main() -> razer::Result<()> {
+    Listener::new()
+        .index(include_str!("count.html"))
+        .start(Handler)
 }
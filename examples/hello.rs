@@ -1,9 +1,9 @@
 use razer::event::event_type::Event::JS;
 use razer::event::handler::EventHandler;
+use razer::event::types::GenericEvent;
 use razer::listener::Listener;
 use razer::send::send;
 use razer::Sender;
-use razer::Value;
 
 #[derive(Copy)]
 pub struct Handler;
@@ -15,11 +15,13 @@ impl Clone for Handler {
 }
 
 impl EventHandler for Handler {
-    fn load(&self, _event: Value, ctx: &Sender) {
+    fn load(&mut self, _event: GenericEvent, ctx: &Sender) {
         send(ctx, JS, "alert(\"Hello\")").unwrap();
     }
 }
 
-fn main() {
-    Listener::new().start(Handler);
+fn main() -> razer::Result<()> {
+    Listener::new()
+        .index(include_str!("hello.html"))
+        .start(Handler)
 }
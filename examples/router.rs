@@ -0,0 +1,39 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+use razer::event::event_type::Event::JS;
+use razer::event::types::{GenericEvent, MouseEvent};
+use razer::listener::Listener;
+use razer::router::Router;
+use razer::send::{broadcast, send};
+use razer::Sender;
+
+fn main() -> razer::Result<()> {
+    let clicks = Arc::new(AtomicU32::new(0));
+    let on_load = clicks.clone();
+    let on_click = clicks;
+
+    let router = Router::new()
+        .on("load", move |_event: GenericEvent, ctx: &Sender| {
+            send(
+                ctx,
+                JS,
+                &format!(
+                    "document.getElementById(\"counter\").innerHTML = {}",
+                    on_load.load(Ordering::Relaxed)
+                ),
+            )
+            .unwrap();
+        })
+        .on("click", move |_event: MouseEvent, ctx: &Sender| {
+            let count = on_click.fetch_add(1, Ordering::Relaxed) + 1;
+            broadcast(
+                ctx,
+                JS,
+                &format!("document.getElementById(\"counter\").innerHTML = {}", count),
+            )
+            .unwrap();
+        });
+
+    Listener::new().start(router)
+}
@@ -0,0 +1,44 @@
+use razer::event::event_type::Event::JS;
+use razer::event::handler::EventHandler;
+use razer::event::types::{GenericEvent, MouseEvent};
+use razer::listener::Listener;
+use razer::send::send;
+use razer::session::from_fn;
+use razer::Sender;
+
+// every connection gets its own Clicks so nothing has to be shared
+#[derive(Default)]
+pub struct Clicks {
+    count: u32,
+}
+
+impl Clicks {
+    fn update(&self, ctx: &Sender) {
+        send(
+            ctx,
+            JS,
+            &format!(
+                "document.getElementById(\"counter\").innerHTML = {}",
+                self.count
+            ),
+        )
+        .unwrap();
+    }
+}
+
+impl EventHandler for Clicks {
+    fn load(&mut self, _event: GenericEvent, ctx: &Sender) {
+        self.update(ctx);
+    }
+
+    fn click(&mut self, _event: MouseEvent, ctx: &Sender) {
+        self.count += 1;
+        self.update(ctx);
+    }
+}
+
+fn main() -> razer::Result<()> {
+    Listener::new()
+        .index(include_str!("session.html"))
+        .start(from_fn(Clicks::default))
+}
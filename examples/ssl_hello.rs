@@ -1,9 +1,9 @@
 use razer::event::event_type::Event::JS;
 use razer::event::handler::EventHandler;
+use razer::event::types::GenericEvent;
 use razer::listener::Listener;
 use razer::send::send;
 use razer::Sender;
-use razer::Value;
 
 #[derive(Copy)]
 pub struct Handler;
@@ -15,14 +15,15 @@ impl Clone for Handler {
 }
 
 impl EventHandler for Handler {
-    fn load(&self, _event: Value, ctx: &Sender) {
+    fn load(&mut self, _event: GenericEvent, ctx: &Sender) {
         send(ctx, JS, "alert(\"Hello\")").unwrap();
     }
 }
 
-fn main() {
+fn main() -> razer::Result<()> {
     Listener::new()
-        .with_cert("certificate.crt")
-        .with_key("privateKey.key")
-        .start(Handler);
+        .with_cert("certificate.crt")?
+        .with_key("privateKey.key")?
+        .index(include_str!("ssl_hello.html"))
+        .start(Handler)
 }
@@ -0,0 +1,82 @@
+use razer::event::handler::EventHandler;
+use razer::event::types::{GenericEvent, InputEvent, KeyboardEvent, MouseEvent};
+use razer::listener::Listener;
+use razer::session::from_fn;
+use razer::vdom::{LiveView, Node};
+use razer::Sender;
+
+// only what changed is sent, so the text box keeps its focus and what was typed
+pub struct Todo {
+    items: Vec<String>,
+    typed: String,
+    view: LiveView,
+}
+
+impl Todo {
+    fn new() -> Todo {
+        Todo {
+            items: Vec::new(),
+            typed: String::new(),
+            view: LiveView::new("#app"),
+        }
+    }
+
+    fn render(&mut self, ctx: &Sender) {
+        let items = self.items.iter().enumerate().map(|(i, item)| {
+            Node::element("li").child(Node::text(item)).child(
+                Node::element("button")
+                    .attr("data-index", &i.to_string())
+                    .child(Node::text("done")),
+            )
+        });
+        let tree = Node::element("div")
+            .child(
+                Node::element("input")
+                    .attr("id", "new")
+                    .attr("value", &self.typed),
+            )
+            .child(Node::element("p").child(Node::text(&format!("{} left", self.items.len()))))
+            .child(Node::element("ul").children(items));
+        self.view.render(ctx, tree).unwrap();
+    }
+}
+
+impl EventHandler for Todo {
+    fn load(&mut self, _event: GenericEvent, ctx: &Sender) {
+        self.view.reset();
+        self.render(ctx);
+    }
+
+    fn input(&mut self, event: InputEvent, ctx: &Sender) {
+        if event.target.id == "new" {
+            // the view has to know what was typed so it can clear it later
+            self.typed = event.target.value.unwrap_or_default();
+            self.render(ctx);
+        }
+    }
+
+    fn keydown(&mut self, event: KeyboardEvent, ctx: &Sender) {
+        if event.key == "Enter" && !self.typed.is_empty() {
+            self.items.push(std::mem::take(&mut self.typed));
+            self.render(ctx);
+        }
+    }
+
+    fn click(&mut self, event: MouseEvent, ctx: &Sender) {
+        let index = event
+            .target
+            .attributes
+            .get("data-index")
+            .and_then(|i| i.parse::<usize>().ok());
+        if let Some(index) = index.filter(|index| *index < self.items.len()) {
+            self.items.remove(index);
+            self.render(ctx);
+        }
+    }
+}
+
+fn main() -> razer::Result<()> {
+    Listener::new()
+        .index(include_str!("todo.html"))
+        .start(from_fn(Todo::new))
+}
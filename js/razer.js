@@ -6,38 +6,137 @@ Object.keys(window).forEach(key => {
     }
 })
 
-const socket = new WebSocket(location.protocol === 'https:' ? "wss://" : "ws://" + window.location.hostname + ":2794");
+// the razer server fills these in when it serves /razer.js, the defaults are for a copy that is
+// hosted somewhere else
+const RAZER_HOST = window.location.hostname + ":2794";
+const RAZER_PATH = "/";
+// the query parameter for the csrf token from <meta name="razer-csrf">, if the server wants one
+const RAZER_CSRF = null;
+
+// the id of the session from the server, when the socket drops it is opened again with it so
+// the website gets the same session back
+var session = null;
+var retries = 0;
+var socket;
+
+function add_param(url, name, value) {
+    return url + (url.includes("?") ? "&" : "?") + encodeURIComponent(name) + "=" + encodeURIComponent(value);
+}
+
+function socket_url() {
+    let url = (location.protocol === 'https:' ? "wss://" : "ws://") + RAZER_HOST + RAZER_PATH;
+    const token = document.querySelector('meta[name="razer-csrf"]');
+    if (RAZER_CSRF !== null && token)
+        url = add_param(url, RAZER_CSRF, token.content);
+    if (session !== null)
+        url = add_param(url, "razer_session", session);
+    return url;
+}
+
+function connect() {
+    socket = new WebSocket(socket_url());
+    socket.onopen = function() {
+        retries = 0;
+        for (var i = 0; i < messages.length; i++) {
+            socket.send(messages[i]);
+        }
+        messages = [];
+    };
+    socket.onmessage = on_message;
+    socket.onclose = function(event) {
+        // only a server that keeps sessions can take the website back, 1001 is sent when the
+        // server shuts down and it will not
+        if (session === null || event.code === 1001)
+            return;
+        setTimeout(connect, Math.min(10000, 250 * Math.pow(2, retries++)));
+    };
+}
+
+const PROTOCOL_VERSION = 1;
+
+function send_message(message) {
+    message.v = PROTOCOL_VERSION;
+    var send = JSON.stringify(message);
+    // kept until the socket is open again
+    if (socket.readyState === WebSocket.OPEN)
+        socket.send(send);
+    else
+        messages.push(send);
+}
+
+// the events the server wants, this is null until it says which ones and "all" if it wants every
+// event, nothing else is turned into json or sent
+var subscribed = null;
+// the last event of every type from before the server said which ones it wants, like `load`
+var early = new Map();
+
+function wanted(name) {
+    return subscribed === "all" || (subscribed !== null && subscribed.has(name));
+}
+
+function send_event(event) {
+    send_message({type: "event", name: event.type, event: stringify_object(event)});
+}
 
 for (var i = 0; i < events.length; i++) {
     window.addEventListener(events[i], function(event) {
-        var send = "{\"event_name\":\"" + event.type + "\",\"event\":" + stringify_object(event) + "}";
-        try {
-            socket.send("{\"event_name\":\"" + event.type + "\",\"event\":" + stringify_object(event) + "}");
-        }
-        catch(err) {
-            messages.push(send);
+        if (subscribed === null) {
+            early.delete(event.type);
+            early.set(event.type, event);
+        } else if (wanted(event.type)) {
+            send_event(event);
         }
     })
 }
 
-socket.onopen = function() {
-    for (var i = 0; i < messages.length; i++) {
-        socket.send(messages[i]);
-    }
+function subscribe(names) {
+    subscribed = names === null ? "all" : new Set(names);
+    early.forEach(function(event, name) {
+        if (wanted(name))
+            send_event(event);
+    });
+    early.clear();
 }
 
-socket.onmessage = function(event) {
-    let data = JSON.parse(event.data);
-    if (data.name === "js") {
-        eval(data.data);
-    } else if (data.name === "html") {
-        document.querySelector("body").innerHTML = data.data;
-    } else if (data.name === "eval") {
-        var run = data.data;
-        socket.send("{\"event_name\":\"eval\",\"event\":\"" + eval(run).replaceAll("\"", "\\\"") + "\",\"data\":\"" + run.replaceAll("\"", "\\\"") + "\"}");
+window.razer = {
+    // send an event that your website made up, the server can handle it with `Listener::on`
+    emit: function(name, payload) {
+        send_message({type: "event", name: name, event: payload === undefined ? null : payload});
     }
 };
 
+function on_message(event) {
+    let data = JSON.parse(event.data);
+    if (data.v !== PROTOCOL_VERSION) {
+        console.error("razer: the server speaks protocol version " + data.v + " but this script speaks " + PROTOCOL_VERSION);
+        return;
+    }
+    if (data.type === "js") {
+        eval(data.code);
+    } else if (data.type === "html") {
+        document.querySelector("body").innerHTML = data.html;
+    } else if (data.type === "dom") {
+        apply_commands(data.commands);
+    } else if (data.type === "patch") {
+        apply_patches(data.root, data.patches);
+    } else if (data.type === "subscribe") {
+        subscribe(data.events);
+    } else if (data.type === "session") {
+        // the server could not take the website back, so everything on it is out of date
+        if (session !== null && !data.resumed) {
+            location.reload();
+            return;
+        }
+        session = data.id;
+    } else if (data.type === "eval" && data.id !== undefined) {
+        run_eval(data.id, data.code);
+    } else if (data.type === "eval") {
+        send_message({type: "eval", result: String(eval(data.code)), code: data.code});
+    }
+}
+
+connect();
+
 function stringify_object(object, depth = 0, max_depth = 2) {
     // change max_depth to see more levels, for a touch event, 2 is good
     if (depth > max_depth)
@@ -47,10 +146,8 @@ function stringify_object(object, depth = 0, max_depth = 2) {
     for (let key in object) {
         let value = object[key];
         if (value instanceof Node)
-            // specify which properties you want to see from the node
-            value = {
-                id: value.id
-            };
+            // the server matches selectors against the target and its parents
+            value = describe_node(value, depth === 0 && key === "target");
         else if (value instanceof Window)
             value = 'Window';
         else if (value instanceof Object)
@@ -59,5 +156,184 @@ function stringify_object(object, depth = 0, max_depth = 2) {
         obj[key] = value;
     }
 
-    return depth ? obj : JSON.stringify(obj);
-}
\ No newline at end of file
+    return obj;
+}
+
+function describe_element(node) {
+    const described = {
+        id: node.id || "",
+        tag: node.tagName ? node.tagName.toLowerCase() : "",
+        classes: node.classList ? Array.from(node.classList) : [],
+        attributes: {}
+    };
+    if (node.attributes) {
+        for (let i = 0; i < node.attributes.length; i++)
+            described.attributes[node.attributes[i].name] = node.attributes[i].value;
+    }
+    return described;
+}
+
+function describe_node(node, with_parents) {
+    // specify which properties you want to see from the node
+    const described = describe_element(node);
+    if (typeof node.value === "string")
+        described.value = node.value;
+    if (node instanceof HTMLMediaElement) {
+        described.currentTime = node.currentTime;
+        described.duration = isFinite(node.duration) ? node.duration : null;
+        described.paused = node.paused;
+        described.muted = node.muted;
+        described.volume = node.volume;
+    }
+    if (with_parents) {
+        described.parents = [];
+        for (let parent = node.parentElement; parent; parent = parent.parentElement)
+            described.parents.push(describe_element(parent));
+    }
+    return described;
+}
+
+function apply_commands(commands) {
+    for (let i = 0; i < commands.length; i++) {
+        const command = commands[i];
+        if (command.op === "focus") {
+            const element = document.querySelector(command.selector);
+            if (element)
+                element.focus();
+            continue;
+        }
+        document.querySelectorAll(command.selector).forEach(function(element) {
+            switch (command.op) {
+                case "set_html":
+                    element.innerHTML = command.html;
+                    break;
+                case "set_text":
+                    element.textContent = command.text;
+                    break;
+                case "set_attribute":
+                    element.setAttribute(command.name, command.value);
+                    break;
+                case "remove_attribute":
+                    element.removeAttribute(command.name);
+                    break;
+                case "add_class":
+                    element.classList.add(command.class);
+                    break;
+                case "remove_class":
+                    element.classList.remove(command.class);
+                    break;
+                case "set_value":
+                    element.value = command.value;
+                    break;
+                case "insert":
+                    element.insertAdjacentHTML(command.position, command.html);
+                    break;
+                case "remove":
+                    element.remove();
+                    break;
+                case "set_style":
+                    element.style.setProperty(command.property, command.value);
+                    break;
+                case "remove_style":
+                    element.style.removeProperty(command.property);
+                    break;
+            }
+        });
+    }
+}
+
+function build_node(node, svg) {
+    if (node.text !== undefined)
+        return document.createTextNode(node.text);
+    const element = node.element;
+    svg = svg || element.tag === "svg";
+    const built = svg ? document.createElementNS("http://www.w3.org/2000/svg", element.tag) : document.createElement(element.tag);
+    for (let name in element.attributes)
+        set_attribute(built, name, element.attributes[name]);
+    for (let i = 0; i < element.children.length; i++)
+        built.appendChild(build_node(element.children[i], svg));
+    return built;
+}
+
+function set_attribute(element, name, value) {
+    element.setAttribute(name, value);
+    // the attribute is only the starting value after the user changed the input
+    if (name === "value")
+        element.value = value;
+    else if (name === "checked")
+        element.checked = true;
+}
+
+function apply_patches(root, patches) {
+    const container = document.querySelector(root);
+    if (!container) {
+        console.error("razer: nothing matches " + root);
+        return;
+    }
+    for (let i = 0; i < patches.length; i++) {
+        const patch = patches[i];
+        // the path starts at the root of the view which is the first child of the container
+        let node = container.firstChild;
+        for (let j = 0; node && j < patch.path.length; j++)
+            node = node.childNodes[patch.path[j]];
+        if (patch.op === "replace" && patch.path.length === 0) {
+            container.replaceChildren(build_node(patch.node, container instanceof SVGElement));
+            continue;
+        }
+        if (!node) {
+            console.error("razer: the page does not match the last render");
+            return;
+        }
+        switch (patch.op) {
+            case "replace":
+                node.replaceWith(build_node(patch.node, node.parentNode instanceof SVGElement));
+                break;
+            case "append":
+                node.appendChild(build_node(patch.node, node instanceof SVGElement));
+                break;
+            case "remove":
+                node.remove();
+                break;
+            case "set_attribute":
+                set_attribute(node, patch.name, patch.value);
+                break;
+            case "remove_attribute":
+                node.removeAttribute(patch.name);
+                if (patch.name === "checked")
+                    node.checked = false;
+                break;
+            case "set_text":
+                node.textContent = patch.text;
+                break;
+        }
+    }
+}
+
+function run_eval(id, code) {
+    const reply = function(answer) {
+        answer.type = "eval_result";
+        answer.id = id;
+        send_message(answer);
+    };
+    const fail = function(err) {
+        reply({
+            error: {
+                name: err && err.name ? err.name : "Error",
+                message: err && err.message !== undefined ? String(err.message) : String(err),
+                stack: err && err.stack ? String(err.stack) : null
+            }
+        });
+    };
+    try {
+        Promise.resolve(eval(code)).then(function(result) {
+            try {
+                reply({result: result === undefined ? null : result});
+            } catch (err) {
+                // the result could not be turned into json
+                fail(err);
+            }
+        }, fail);
+    } catch (err) {
+        fail(err);
+    }
+}
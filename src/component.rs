@@ -0,0 +1,458 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use log::debug;
+use serde_json::Value;
+
+use crate::connections::ConnectionInfo;
+use crate::error::Result;
+use crate::event::handler::EventHandler;
+use crate::event::subscription::{normalize, subscribe};
+use crate::event::types::{BrowserEvent, InputEvent, KeyboardEvent, MouseEvent, Target};
+use crate::protocol::ServerMessage;
+use crate::send::send_message;
+use crate::vdom::{LiveView, Node, Patch};
+
+/// What a component renders to
+pub type Html = Node;
+
+/// The id of the component that is put in `Page::new`
+pub const ROOT: &str = "root";
+
+const ATTRIBUTE: &str = "data-component";
+
+// the events that `Component::on_event` handles by default
+const EVENTS: &[&str] = &["click", "dblclick", "input", "change", "keydown", "keyup"];
+
+/// A part of the website that keeps its own state and renders itself <br />
+/// return true from an event method when the state changed and razer will render the component
+/// again and only send what changed inside of it
+/// ```
+/// use razer::component::{Component, Html};
+/// use razer::event::types::MouseEvent;
+/// use razer::vdom::Node;
+/// use razer::Sender;
+///
+/// #[derive(Default)]
+/// pub struct Counter {
+///     count: u32,
+/// }
+///
+/// impl Component for Counter {
+///     fn render(&self) -> Html {
+///         Node::element("button").child(Node::text(&format!("clicked {} times", self.count)))
+///     }
+///
+///     fn click(&mut self, _event: MouseEvent, _ctx: &Sender) -> bool {
+///         self.count += 1;
+///         true
+///     }
+/// }
+/// ```
+/// events bubble like they do on the website, so an event is given to the component it happened
+/// in first, then to the component around that one and so on up to the root
+pub trait Component: Send + 'static {
+    fn render(&self) -> Html;
+
+    fn click(&mut self, _event: MouseEvent, _ctx: &crate::Sender) -> bool {
+        false
+    }
+    fn dblclick(&mut self, _event: MouseEvent, _ctx: &crate::Sender) -> bool {
+        false
+    }
+    fn input(&mut self, _event: InputEvent, _ctx: &crate::Sender) -> bool {
+        false
+    }
+    fn change(&mut self, _event: InputEvent, _ctx: &crate::Sender) -> bool {
+        false
+    }
+    fn keydown(&mut self, _event: KeyboardEvent, _ctx: &crate::Sender) -> bool {
+        false
+    }
+    fn keyup(&mut self, _event: KeyboardEvent, _ctx: &crate::Sender) -> bool {
+        false
+    }
+
+    /// The events this component uses, the website only sends the events that a component of
+    /// the page uses <br />
+    /// the default is every event that has a method here, add to it when `on_event` handles more
+    fn subscriptions(&self) -> Vec<String> {
+        EVENTS.iter().map(|event| event.to_string()).collect()
+    }
+
+    /// This is called for every event, override it to handle an event that does not have its own
+    /// method
+    fn on_event(&mut self, name: &str, event: &Value, ctx: &crate::Sender) -> bool {
+        match name {
+            "click" => self.click(BrowserEvent::from_value(event.clone()), ctx),
+            "dblclick" => self.dblclick(BrowserEvent::from_value(event.clone()), ctx),
+            "input" => self.input(BrowserEvent::from_value(event.clone()), ctx),
+            "change" => self.change(BrowserEvent::from_value(event.clone()), ctx),
+            "keydown" => self.keydown(BrowserEvent::from_value(event.clone()), ctx),
+            "keyup" => self.keyup(BrowserEvent::from_value(event.clone()), ctx),
+            _ => false,
+        }
+    }
+}
+
+impl Node {
+    /// The place where the component with the id is put, the component has to be added to the
+    /// `Page` with `Page::with` or `Page::insert`
+    pub fn component(id: &str) -> Node {
+        Node::element("div")
+            .attr(ATTRIBUTE, id)
+            .attr("style", "display: contents")
+    }
+}
+
+struct Mounted {
+    component: Box<dyn Component>,
+    view: LiveView,
+}
+
+/// The components of one connection, this is an `EventHandler` so it can be given to
+/// `Listener::start`
+/// ```no_run
+/// use razer::component::{Component, Html, Page};
+/// use razer::listener::Listener;
+/// use razer::session::from_fn;
+/// use razer::vdom::Node;
+///
+/// pub struct App;
+///
+/// impl Component for App {
+///     fn render(&self) -> Html {
+///         Node::element("main")
+///             .child(Node::element("h1").child(Node::text("two counters")))
+///             .child(Node::component("left"))
+///             .child(Node::component("right"))
+///     }
+/// }
+///
+/// # #[derive(Default)]
+/// # pub struct Counter;
+/// # impl Component for Counter {
+/// #     fn render(&self) -> Html {
+/// #         Node::text("")
+/// #     }
+/// # }
+/// Listener::new().start(from_fn(|| {
+///     Page::new("#app", App)
+///         .with("left", Counter::default())
+///         .with("right", Counter::default())
+/// }))
+/// .unwrap();
+/// ```
+/// everything is rendered on the `load` event
+pub struct Page {
+    components: HashMap<String, Mounted>,
+    // the events the website was told to send
+    subscribed: Vec<String>,
+}
+
+impl Page {
+    /// The root component is put inside of the first element that matches the selector
+    pub fn new<C: Component>(root: &str, component: C) -> Page {
+        let mut components = HashMap::new();
+        components.insert(
+            ROOT.to_string(),
+            Mounted {
+                component: Box::new(component),
+                view: LiveView::new(root),
+            },
+        );
+        Page {
+            components,
+            subscribed: Vec::new(),
+        }
+    }
+
+    /// Add a component and give back the page so calls can be chained
+    #[must_use]
+    pub fn with<C: Component>(mut self, id: &str, component: C) -> Page {
+        self.insert(id, component);
+        self
+    }
+
+    /// Add a component, or replace the one that has the same id <br />
+    /// it is shown once a component that renders `Node::component(id)` is rendered
+    pub fn insert<C: Component>(&mut self, id: &str, component: C) {
+        self.components.insert(
+            id.to_string(),
+            Mounted {
+                component: Box::new(component),
+                view: LiveView::new(&selector(id)),
+            },
+        );
+    }
+
+    /// Remove a component, the root can not be removed
+    pub fn remove(&mut self, id: &str) -> bool {
+        id != ROOT && self.components.remove(id).is_some()
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.components.contains_key(id)
+    }
+
+    /// The ids of every component
+    pub fn ids(&self) -> Vec<String> {
+        self.components.keys().cloned().collect()
+    }
+
+    /// Render the component again and send what changed, the components inside of it are only
+    /// rendered if their place on the website was made again <br />
+    /// nothing is sent if there is no component with the id
+    pub fn render(&mut self, id: &str, ctx: &crate::Sender) -> Result<()> {
+        let mounted = match self.components.get_mut(id) {
+            Some(mounted) => mounted,
+            None => return Ok(()),
+        };
+        let patches = mounted.view.update(mounted.component.render());
+        if patches.is_empty() {
+            return Ok(());
+        }
+        let children = new_components(&patches);
+        let message = ServerMessage::Patch {
+            root: mounted.view.root().to_string(),
+            patches,
+        };
+        if let Err(err) = send_message(ctx, &message) {
+            mounted.view.reset();
+            return Err(err);
+        }
+        for child in children {
+            if let Some(mounted) = self.components.get_mut(&child) {
+                mounted.view.reset();
+                self.render(&child, ctx)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Forget what was sent and render every component from the root
+    pub fn render_all(&mut self, ctx: &crate::Sender) -> Result<()> {
+        for mounted in self.components.values_mut() {
+            mounted.view.reset();
+        }
+        self.render(ROOT, ctx)
+    }
+
+    /// Every event a component uses and `load`
+    pub fn events(&self) -> Vec<String> {
+        let mut events = vec!["load".to_string()];
+        for mounted in self.components.values() {
+            events.extend(mounted.component.subscriptions());
+        }
+        normalize(events)
+    }
+
+    // components can be added and removed by an event, so the website is told when that changes
+    // what it has to send
+    fn resubscribe(&mut self, ctx: &crate::Sender) {
+        let events = self.events();
+        if events != self.subscribed {
+            match subscribe(ctx, &events) {
+                Ok(()) => self.subscribed = events,
+                Err(err) => debug!("could not send the events: {}", err),
+            }
+        }
+    }
+
+    fn chain(&self, event: &Value) -> Vec<String> {
+        let target = serde_json::from_value::<Target>(event["target"].clone()).unwrap_or_default();
+        let mut chain = components_around(&target);
+        chain.retain(|id| id != ROOT && self.components.contains_key(id));
+        chain.push(ROOT.to_string());
+        chain
+    }
+}
+
+impl EventHandler for Page {
+    fn on_connect(&mut self, _info: ConnectionInfo, _ctx: &crate::Sender) {
+        // the listener sends `subscriptions` right after this
+        self.subscribed = self.events();
+    }
+
+    fn subscriptions(&self) -> Option<Vec<String>> {
+        Some(self.events())
+    }
+
+    fn on_event(&mut self, name: &str, event: Value, ctx: &crate::Sender) {
+        if name == "load" {
+            if let Err(err) = self.render_all(ctx) {
+                debug!("could not render the page: {}", err);
+            }
+        }
+        for id in self.chain(&event) {
+            let changed = match self.components.get_mut(&id) {
+                Some(mounted) => mounted.component.on_event(name, &event, ctx),
+                None => continue,
+            };
+            if changed {
+                if let Err(err) = self.render(&id, ctx) {
+                    debug!("could not render {}: {}", id, err);
+                }
+            }
+        }
+        self.resubscribe(ctx);
+    }
+}
+
+impl fmt::Debug for Page {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Page")
+            .field("components", &self.ids())
+            .finish()
+    }
+}
+
+fn selector(id: &str) -> String {
+    let mut selector = format!("[{}=\"", ATTRIBUTE);
+    for c in id.chars() {
+        if c == '"' || c == '\\' {
+            selector.push('\\');
+        }
+        selector.push(c);
+    }
+    selector.push_str("\"]");
+    selector
+}
+
+// the ids of the components from the closest to the furthest from the target
+fn components_around(target: &Target) -> Vec<String> {
+    target
+        .elements()
+        .iter()
+        .filter_map(|element| element.attributes.get(ATTRIBUTE).cloned())
+        .collect()
+}
+
+// the components whose place was added to the website by the patches
+fn new_components(patches: &[Patch]) -> Vec<String> {
+    let mut ids = Vec::new();
+    for patch in patches {
+        if let Patch::Replace { node, .. } | Patch::Append { node, .. } = patch {
+            find_components(node, &mut ids);
+        }
+    }
+    ids
+}
+
+fn find_components(node: &Node, ids: &mut Vec<String>) {
+    if let Node::Element {
+        attributes,
+        children,
+        ..
+    } = node
+    {
+        if let Some(id) = attributes.get(ATTRIBUTE) {
+            ids.push(id.clone());
+        }
+        for child in children {
+            find_components(child, ids);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::types::Element;
+
+    #[test]
+    fn new_places_are_found() {
+        let patches = vec![
+            Patch::SetText {
+                path: vec![0],
+                text: "hi".to_string(),
+            },
+            Patch::Replace {
+                path: vec![],
+                node: Node::element("main")
+                    .child(Node::component("a"))
+                    .child(Node::element("div").child(Node::component("b"))),
+            },
+            Patch::Append {
+                path: vec![1],
+                node: Node::component("c"),
+            },
+        ];
+        assert_eq!(new_components(&patches), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn a_place_that_stays_is_not_rendered_again() {
+        let mut view = LiveView::new("#app");
+        let tree = |title: &str| {
+            Node::element("main")
+                .child(Node::text(title))
+                .child(Node::component("counter"))
+        };
+        assert_eq!(new_components(&view.update(tree("one"))), vec!["counter"]);
+        assert!(new_components(&view.update(tree("two"))).is_empty());
+    }
+
+    #[test]
+    fn events_go_to_the_closest_component_first() {
+        let place = |id: &str| {
+            let mut element = Element {
+                tag: "div".to_string(),
+                ..Element::default()
+            };
+            element
+                .attributes
+                .insert(ATTRIBUTE.to_string(), id.to_string());
+            element
+        };
+        let target = Target {
+            tag: "button".to_string(),
+            parents: vec![place("inner"), place("outer"), Element::default()],
+            ..Target::default()
+        };
+        assert_eq!(components_around(&target), vec!["inner", "outer"]);
+    }
+
+    #[test]
+    fn the_page_subscribes_to_what_its_components_use() {
+        struct Canvas;
+        impl Component for Canvas {
+            fn render(&self) -> Html {
+                Node::element("canvas")
+            }
+            fn subscriptions(&self) -> Vec<String> {
+                vec!["mousemove".to_string(), "click".to_string()]
+            }
+        }
+        struct Form;
+        impl Component for Form {
+            fn render(&self) -> Html {
+                Node::element("form")
+            }
+        }
+        let mut page = Page::new("#app", Canvas);
+        assert_eq!(page.events(), vec!["click", "load", "mousemove"]);
+        page.insert("form", Form);
+        assert_eq!(
+            page.events(),
+            vec![
+                "change",
+                "click",
+                "dblclick",
+                "input",
+                "keydown",
+                "keyup",
+                "load",
+                "mousemove"
+            ]
+        );
+        page.remove("form");
+        assert_eq!(page.events(), vec!["click", "load", "mousemove"]);
+    }
+
+    #[test]
+    fn ids_are_quoted_in_the_selector() {
+        assert_eq!(selector("a"), "[data-component=\"a\"]");
+        assert_eq!(selector("a\"]b\\"), "[data-component=\"a\\\"]b\\\\\"]");
+    }
+}
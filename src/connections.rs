@@ -1,29 +1,177 @@
+use std::collections::HashMap;
+
+use razer_ws::util::Token;
+use razer_ws::Handshake;
+
+use crate::identity::Identity;
+
 /// This will return a bool on if the connection is closed
 /// ```
 /// use razer::Sender;
 /// use razer::connections::closed;
-/// assert_eq!(closed(ctx), false);
-/// // connection was closed here
-/// assert_eq!(closed(ctx), true);
+/// fn click(ctx: &Sender) {
+///     if closed(ctx) {
+///         println!("the connection was closed");
+///     }
+/// }
 /// ```
-pub fn closed(ctx: &razer_ws::Sender) -> bool {
-    !crate::listener::CONNECTIONS
-        .lock()
-        .unwrap()
-        .contains_key(&ctx.token().0)
+pub fn closed(ctx: &crate::Sender) -> bool {
+    !open(ctx)
 }
 
 /// This will return a bool on if the connection is open
 /// ```
 /// use razer::Sender;
 /// use razer::connections::open;
-/// assert_eq!(open(ctx), true);
-/// // connection was closed here
-/// assert_eq!(open(ctx), false);
+/// fn click(ctx: &Sender) {
+///     if open(ctx) {
+///         println!("the connection is still open");
+///     }
+/// }
 /// ```
-pub fn open(ctx: &razer_ws::Sender) -> bool {
-    crate::listener::CONNECTIONS
+pub fn open(ctx: &crate::Sender) -> bool {
+    let (token, connection_id) = ctx.socket();
+    ctx.registry()
+        .connections
         .lock()
         .unwrap()
-        .contains_key(&ctx.token().0)
+        .get(&token)
+        .is_some_and(|open| *open == connection_id)
+}
+
+/// How many connections the server of `ctx` has open
+pub fn count(ctx: &crate::Sender) -> usize {
+    ctx.registry().connections.lock().unwrap().len()
+}
+
+/// The token of every connection the server of `ctx` has open, they can be used with `send_to`
+pub fn tokens(ctx: &crate::Sender) -> Vec<Token> {
+    ctx.registry()
+        .connections
+        .lock()
+        .unwrap()
+        .keys()
+        .map(|token| Token::from(*token))
+        .collect()
+}
+
+/// What razer knows about a client when it connects, this is given to `EventHandler::on_connect`
+/// ```
+/// use razer::connections::ConnectionInfo;
+/// use razer::Sender;
+/// fn on_connect(info: ConnectionInfo, _ctx: &Sender) {
+///     println!("{:?} opened {} from {:?}", info.remote_addr, info.resource, info.origin);
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConnectionInfo {
+    /// The path the socket was opened on like `/` or `/chat?room=1`
+    pub resource: String,
+    pub origin: Option<String>,
+    pub user_agent: Option<String>,
+    pub cookies: HashMap<String, String>,
+    /// This is the ip of the client, if the request has a `X-Forwarded-For` or `Forwarded` header
+    /// that will be used instead of the ip of the socket
+    pub remote_addr: Option<String>,
+    /// Who opened the connection, if the `Listener` has an `authenticate` hook
+    pub identity: Option<Identity>,
+}
+
+impl ConnectionInfo {
+    /// Get the value of a cookie by its name
+    pub fn cookie(&self, name: &str) -> Option<&str> {
+        self.cookies.get(name).map(String::as_str)
+    }
+}
+
+impl From<&Handshake> for ConnectionInfo {
+    fn from(shake: &Handshake) -> Self {
+        let header = |name: &str| {
+            shake
+                .request
+                .header(name)
+                .map(|value| String::from_utf8_lossy(value).into_owned())
+        };
+        ConnectionInfo {
+            resource: shake.request.resource().to_string(),
+            origin: header("origin"),
+            user_agent: header("user-agent"),
+            cookies: header("cookie")
+                .map(|cookies| parse_cookies(&cookies))
+                .unwrap_or_default(),
+            remote_addr: shake.remote_addr().ok().flatten(),
+            identity: None,
+        }
+    }
+}
+
+pub(crate) fn parse_cookies(header: &str) -> HashMap<String, String> {
+    header
+        .split(';')
+        .filter_map(|cookie| {
+            let (name, value) = cookie.split_once('=')?;
+            Some((name.trim().to_string(), value.trim().to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use razer_ws::{Request, Response};
+
+    use super::*;
+
+    fn handshake(headers: &str) -> Handshake {
+        let raw = format!(
+            "GET /chat?room=1 HTTP/1.1\r\nHost: example.com\r\nUpgrade: websocket\r\n\
+             Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n{}\r\n",
+            headers
+        );
+        let request = Request::parse(raw.as_bytes()).unwrap().unwrap();
+        Handshake {
+            response: Response::from_request(&request).unwrap(),
+            request,
+            peer_addr: Some("10.0.0.1:5000".parse().unwrap()),
+            local_addr: None,
+        }
+    }
+
+    #[test]
+    fn connection_info_comes_from_the_handshake() {
+        let info = ConnectionInfo::from(&handshake(
+            "Origin: https://example.com\r\nUser-Agent: test\r\nCookie: theme=dark; id=42\r\n",
+        ));
+        assert_eq!(info.resource, "/chat?room=1");
+        assert_eq!(info.origin.as_deref(), Some("https://example.com"));
+        assert_eq!(info.user_agent.as_deref(), Some("test"));
+        assert_eq!(info.cookie("theme"), Some("dark"));
+        assert_eq!(info.cookie("id"), Some("42"));
+        assert_eq!(info.cookie("missing"), None);
+        assert_eq!(info.remote_addr.as_deref(), Some("10.0.0.1"));
+        assert_eq!(info.identity, None);
+    }
+
+    #[test]
+    fn the_forwarded_address_is_used_behind_a_proxy() {
+        let info = ConnectionInfo::from(&handshake("X-Forwarded-For: 1.2.3.4, 10.0.0.1\r\n"));
+        assert_eq!(info.remote_addr.as_deref(), Some("1.2.3.4"));
+        assert_eq!(info.origin, None);
+        assert!(info.cookies.is_empty());
+    }
+
+    #[test]
+    fn only_open_connections_are_counted() {
+        let registry = Arc::default();
+        let (_socket, first) = crate::sender::detached(Arc::clone(&registry), 1, 7);
+        let (_socket, reused) = crate::sender::detached(Arc::clone(&registry), 1, 8);
+        assert!(closed(&first));
+        registry.connections.lock().unwrap().insert(1, 7);
+        assert!(open(&first));
+        // the token was given to a new connection
+        assert!(closed(&reused));
+        assert_eq!(count(&first), 1);
+        assert_eq!(tokens(&first), vec![Token(1)]);
+    }
 }
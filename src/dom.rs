@@ -0,0 +1,211 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::protocol::ServerMessage;
+use crate::send::{broadcast_message, send_message};
+
+/// Where `Command::Insert` puts the html, this is the same as `insertAdjacentHTML`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Position {
+    /// Before the element
+    BeforeBegin,
+    /// Inside the element before its first child
+    AfterBegin,
+    /// Inside the element after its last child
+    BeforeEnd,
+    /// After the element
+    AfterEnd,
+}
+
+/// A change to the website that is done without running any javascript <br />
+/// every command is done to all the elements that match the selector, except `Focus` that only
+/// focuses the first one <br />
+/// the values are sent as they are, so text is never read as html and quotes do not need to be
+/// escaped
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Command {
+    SetHtml {
+        selector: String,
+        html: String,
+    },
+    SetText {
+        selector: String,
+        text: String,
+    },
+    SetAttribute {
+        selector: String,
+        name: String,
+        value: String,
+    },
+    RemoveAttribute {
+        selector: String,
+        name: String,
+    },
+    AddClass {
+        selector: String,
+        class: String,
+    },
+    RemoveClass {
+        selector: String,
+        class: String,
+    },
+    SetValue {
+        selector: String,
+        value: String,
+    },
+    Insert {
+        selector: String,
+        position: Position,
+        html: String,
+    },
+    Remove {
+        selector: String,
+    },
+    SetStyle {
+        selector: String,
+        property: String,
+        value: String,
+    },
+    RemoveStyle {
+        selector: String,
+        property: String,
+    },
+    Focus {
+        selector: String,
+    },
+}
+
+impl Command {
+    pub fn set_html(selector: &str, html: &str) -> Command {
+        Command::SetHtml {
+            selector: selector.to_string(),
+            html: html.to_string(),
+        }
+    }
+
+    pub fn set_text(selector: &str, text: &str) -> Command {
+        Command::SetText {
+            selector: selector.to_string(),
+            text: text.to_string(),
+        }
+    }
+
+    pub fn set_attribute(selector: &str, name: &str, value: &str) -> Command {
+        Command::SetAttribute {
+            selector: selector.to_string(),
+            name: name.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    pub fn remove_attribute(selector: &str, name: &str) -> Command {
+        Command::RemoveAttribute {
+            selector: selector.to_string(),
+            name: name.to_string(),
+        }
+    }
+
+    pub fn add_class(selector: &str, class: &str) -> Command {
+        Command::AddClass {
+            selector: selector.to_string(),
+            class: class.to_string(),
+        }
+    }
+
+    pub fn remove_class(selector: &str, class: &str) -> Command {
+        Command::RemoveClass {
+            selector: selector.to_string(),
+            class: class.to_string(),
+        }
+    }
+
+    pub fn set_value(selector: &str, value: &str) -> Command {
+        Command::SetValue {
+            selector: selector.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    pub fn insert(selector: &str, position: Position, html: &str) -> Command {
+        Command::Insert {
+            selector: selector.to_string(),
+            position,
+            html: html.to_string(),
+        }
+    }
+
+    pub fn remove(selector: &str) -> Command {
+        Command::Remove {
+            selector: selector.to_string(),
+        }
+    }
+
+    pub fn set_style(selector: &str, property: &str, value: &str) -> Command {
+        Command::SetStyle {
+            selector: selector.to_string(),
+            property: property.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    pub fn remove_style(selector: &str, property: &str) -> Command {
+        Command::RemoveStyle {
+            selector: selector.to_string(),
+            property: property.to_string(),
+        }
+    }
+
+    pub fn focus(selector: &str) -> Command {
+        Command::Focus {
+            selector: selector.to_string(),
+        }
+    }
+}
+
+/// This will send the commands to the website, they are done in order
+/// ```
+/// use razer::dom::{apply, Command};
+/// use razer::Sender;
+/// fn click(ctx: &Sender) {
+///     apply(
+///         ctx,
+///         vec![
+///             Command::set_text("#status", "<saved> \"ok\""),
+///             Command::add_class("#save", "done"),
+///             Command::focus("#input"),
+///         ],
+///     )
+///     .unwrap();
+/// }
+/// ```
+/// this will fail with `Error::Closed` if the connection was closed
+pub fn apply(client: &crate::Sender, commands: Vec<Command>) -> Result<()> {
+    send_message(client, &ServerMessage::Dom { commands })
+}
+
+/// The same as `apply` but for every client
+pub fn broadcast(client: &crate::Sender, commands: Vec<Command>) -> Result<()> {
+    broadcast_message(client, &ServerMessage::Dom { commands })
+}
+
+/// This will escape text so it can be put in html that you build yourself
+/// ```
+/// use razer::dom::escape_html;
+/// assert_eq!(escape_html("<b>\"hi\" & 'bye'</b>"), "&lt;b&gt;&quot;hi&quot; &amp; &#39;bye&#39;&lt;/b&gt;");
+/// ```
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,128 @@
+use std::error;
+use std::fmt;
+use std::io;
+
+use openssl::error::ErrorStack;
+use razer_ws::util::Token;
+
+use crate::eval::JsException;
+use crate::protocol::ProtocolError;
+use crate::selector::SelectorError;
+
+/// Everything that can go wrong in razer
+/// ```
+/// use razer::event::event_type::Event::JS;
+/// use razer::send::send;
+/// use razer::{Error, Sender};
+/// fn click(ctx: &Sender) {
+///     match send(ctx, JS, "alert(\"Hello\")") {
+///         Ok(()) => {}
+///         Err(Error::Closed) => println!("they already left"),
+///         Err(err) => println!("{}", err),
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub enum Error {
+    /// The connection was closed
+    Closed,
+    /// No open connection has this token
+    UnknownToken(Token),
+    /// A file could not be read or a thread could not be started
+    Io(io::Error),
+    /// A key or certificate is not valid, or tls could not be set up with them
+    Tls(ErrorStack),
+    /// The server could not listen on the address
+    Bind {
+        address: String,
+        source: Box<razer_ws::Error>,
+    },
+    /// A frame from the website could not be read
+    Protocol(ProtocolError),
+    /// A value could not be turned into json or read from it
+    Serialize(serde_json::Error),
+    /// The code from `eval` threw on the website
+    Exception(JsException),
+    /// The website did not reply to an `eval` in time
+    Timeout,
+    /// A selector could not be parsed
+    Selector(SelectorError),
+    /// Anything else that went wrong in the websocket server, like its queue being full
+    WebSocket(Box<razer_ws::Error>),
+}
+
+/// The same as `std::result::Result` but the error is a `razer::Error`
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Closed => write!(f, "the connection was closed"),
+            Error::UnknownToken(token) => write!(f, "no connection has the token {}", token.0),
+            Error::Io(err) => write!(f, "{}", err),
+            Error::Tls(err) => write!(f, "tls error: {}", err),
+            Error::Bind { address, source } => {
+                write!(f, "could not listen on {}: {}", address, source)
+            }
+            Error::Protocol(err) => write!(f, "{}", err),
+            Error::Serialize(err) => write!(f, "json error: {}", err),
+            Error::Exception(exception) => {
+                write!(f, "{}: {}", exception.name, exception.message)
+            }
+            Error::Timeout => write!(f, "the website did not reply in time"),
+            Error::Selector(err) => write!(f, "{}", err),
+            Error::WebSocket(err) => write!(f, "websocket error: {}", err),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            Error::Tls(err) => Some(err),
+            Error::Bind { source, .. } => Some(source.as_ref()),
+            Error::Protocol(err) => Some(err),
+            Error::Serialize(err) => Some(err),
+            Error::Selector(err) => Some(err),
+            Error::WebSocket(err) => Some(err.as_ref()),
+            Error::Closed | Error::UnknownToken(_) | Error::Exception(_) | Error::Timeout => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<ErrorStack> for Error {
+    fn from(err: ErrorStack) -> Self {
+        Error::Tls(err)
+    }
+}
+
+impl From<ProtocolError> for Error {
+    fn from(err: ProtocolError) -> Self {
+        Error::Protocol(err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Serialize(err)
+    }
+}
+
+impl From<SelectorError> for Error {
+    fn from(err: SelectorError) -> Self {
+        Error::Selector(err)
+    }
+}
+
+impl From<razer_ws::Error> for Error {
+    fn from(err: razer_ws::Error) -> Self {
+        Error::WebSocket(Box::new(err))
+    }
+}
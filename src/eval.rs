@@ -0,0 +1,326 @@
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::{Error, Result};
+use crate::protocol::ServerMessage;
+use crate::registry::Registry;
+use crate::send::send_message;
+
+type Reply = Result<Value>;
+
+enum Waiter {
+    Channel(mpsc::Sender<Reply>),
+    Callback(Box<dyn FnOnce(Reply) + Send>),
+}
+
+pub(crate) struct Pending {
+    token: usize,
+    connection_id: u32,
+    waiter: Waiter,
+}
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// The exception that was thrown on the website while running the code
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JsException {
+    pub name: String,
+    pub message: String,
+    pub stack: Option<String>,
+}
+
+/// The answer to one `eval` call, you can wait for it or give it a callback <br />
+/// if it is dropped without either the result is thrown away when it comes
+pub struct EvalHandle {
+    id: u64,
+    reply: mpsc::Receiver<Reply>,
+    registry: Arc<Registry>,
+}
+
+impl EvalHandle {
+    /// The id that the website will send back with the result
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// This will block until the website replies or the timeout is hit <br />
+    /// it fails with `Error::Exception` if the code threw, `Error::Timeout` if the website did not
+    /// reply in time and `Error::Closed` if the connection closed first
+    pub fn wait(self, timeout: Duration) -> Result<Value> {
+        match self.reply.recv_timeout(timeout) {
+            Ok(reply) => reply,
+            Err(_) => {
+                self.registry.evals.lock().unwrap().remove(&self.id);
+                // the reply could have come in right before it was removed
+                self.reply.try_recv().unwrap_or(Err(Error::Timeout))
+            }
+        }
+    }
+
+    /// The same as `wait` but it will turn the result into `T`
+    /// ```
+    /// use std::time::Duration;
+    /// use razer::eval::eval;
+    /// use razer::Sender;
+    /// fn load(ctx: &Sender) {
+    ///     let width: f64 = eval(ctx, "window.innerWidth")
+    ///         .wait_as(Duration::from_secs(5))
+    ///         .unwrap();
+    /// }
+    /// ```
+    pub fn wait_as<T: DeserializeOwned>(self, timeout: Duration) -> Result<T> {
+        Ok(serde_json::from_value(self.wait(timeout)?)?)
+    }
+
+    /// This will call `callback` on another thread when the website replies <br />
+    /// it does not time out, if the connection closes first it is called with `Error::Closed`
+    pub fn then<F: FnOnce(Result<Value>) + Send + 'static>(self, callback: F) {
+        let mut pending = self.registry.evals.lock().unwrap();
+        match pending.get_mut(&self.id) {
+            Some(entry) => entry.waiter = Waiter::Callback(Box::new(callback)),
+            None => {
+                drop(pending);
+                let reply = self.reply.try_recv().unwrap_or(Err(Error::Closed));
+                thread::spawn(move || callback(reply));
+            }
+        }
+    }
+}
+
+// nothing can get the result anymore, so it is not kept until the connection closes, a callback
+// from `then` stays until it is called
+impl Drop for EvalHandle {
+    fn drop(&mut self) {
+        let mut evals = self.registry.evals.lock().unwrap();
+        if let Some(Pending {
+            waiter: Waiter::Channel(_),
+            ..
+        }) = evals.get(&self.id)
+        {
+            evals.remove(&self.id);
+        }
+    }
+}
+
+impl fmt::Debug for EvalHandle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EvalHandle").field("id", &self.id).finish()
+    }
+}
+
+/// This will run the code on the website and give you a handle to its result
+/// ```
+/// use std::time::Duration;
+/// use razer::eval::eval;
+/// use razer::Sender;
+/// fn keydown(ctx: &Sender) {
+///     match eval(ctx, "document.getElementById(\"input\").value").wait(Duration::from_secs(5)) {
+///         Ok(value) => println!("the input has {}", value),
+///         Err(err) => println!("{}", err),
+///     }
+/// }
+/// ```
+/// if the code returns a promise the result is what the promise resolves to
+pub fn eval(ctx: &crate::Sender, code: &str) -> EvalHandle {
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let (sender, reply) = mpsc::channel();
+    {
+        // the socket is read while the evals are locked, so a session that is resumed right now
+        // has the eval moved to its new socket by `moved`
+        let mut evals = ctx.registry().evals.lock().unwrap();
+        let (token, connection_id) = ctx.socket();
+        evals.insert(
+            id,
+            Pending {
+                token,
+                connection_id,
+                waiter: Waiter::Channel(sender),
+            },
+        );
+    }
+    let message = ServerMessage::Eval {
+        id: Some(id),
+        code: code.to_string(),
+    };
+    if send_message(ctx, &message).is_err() {
+        resolve(ctx.registry(), id, Err(Error::Closed));
+    }
+    EvalHandle {
+        id,
+        reply,
+        registry: ctx.registry().clone(),
+    }
+}
+
+// called by the listener when the website replies, a client can only answer its own evals
+pub(crate) fn reply(ctx: &crate::Sender, id: u64, result: Value, error: Option<JsException>) {
+    match ctx.registry().evals.lock().unwrap().get(&id) {
+        Some(pending)
+            if pending.token == ctx.token().0 && pending.connection_id == ctx.connection_id() => {}
+        _ => return,
+    }
+    let reply = match error {
+        Some(exception) => Err(Error::Exception(exception)),
+        None => Ok(result),
+    };
+    resolve(ctx.registry(), id, reply);
+}
+
+// called by the listener when a connection closes so nothing waits forever, with
+// `Listener::resume` it is called once the session ends
+pub(crate) fn close(ctx: &crate::Sender) {
+    let (token, connection_id) = ctx.socket();
+    let ids: Vec<u64> = ctx
+        .registry()
+        .evals
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(_, pending)| pending.token == token && pending.connection_id == connection_id)
+        .map(|(id, _)| *id)
+        .collect();
+    for id in ids {
+        resolve(ctx.registry(), id, Err(Error::Closed));
+    }
+}
+
+// called by the listener when a session is resumed on a new socket, the website answers the
+// evals it was sent while it was gone from there
+pub(crate) fn moved(from: (usize, u32), to: &crate::Sender) {
+    for pending in to.registry().evals.lock().unwrap().values_mut() {
+        if (pending.token, pending.connection_id) == from {
+            pending.token = to.token().0;
+            pending.connection_id = to.connection_id();
+        }
+    }
+}
+
+fn resolve(registry: &Registry, id: u64, reply: Reply) {
+    let pending = registry.evals.lock().unwrap().remove(&id);
+    if let Some(pending) = pending {
+        match pending.waiter {
+            Waiter::Channel(sender) => {
+                let _ = sender.send(reply);
+            }
+            Waiter::Callback(callback) => {
+                thread::spawn(move || callback(reply));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn replies_are_matched_by_id() {
+        let registry: Arc<Registry> = Arc::default();
+        registry.connections.lock().unwrap().insert(1, 7);
+        let (_socket, ctx) = crate::sender::detached(registry.clone(), 1, 7);
+        let (_other, other) = crate::sender::detached(registry.clone(), 2, 9);
+        let (first, second) = (eval(&ctx, "1"), eval(&ctx, "2"));
+        assert_ne!(first.id(), second.id());
+        // a client can not answer the evals of another one
+        reply(&other, second.id(), json!("stolen"), None);
+        reply(&ctx, second.id(), json!(2), None);
+        reply(&ctx, first.id(), json!(1), None);
+        assert_eq!(second.wait_as::<u32>(Duration::from_secs(1)).unwrap(), 2);
+        assert_eq!(first.wait(Duration::from_secs(1)).unwrap(), json!(1));
+        assert!(registry.evals.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn exceptions_and_timeouts_are_errors() {
+        let registry: Arc<Registry> = Arc::default();
+        registry.connections.lock().unwrap().insert(1, 7);
+        let (_socket, ctx) = crate::sender::detached(registry.clone(), 1, 7);
+        let thrown = eval(&ctx, "x.y");
+        let exception = JsException {
+            name: "TypeError".to_string(),
+            message: "x is undefined".to_string(),
+            stack: None,
+        };
+        reply(&ctx, thrown.id(), Value::Null, Some(exception.clone()));
+        assert!(matches!(
+            thrown.wait(Duration::from_secs(1)),
+            Err(Error::Exception(thrown)) if thrown == exception
+        ));
+        let slow = eval(&ctx, "new Promise(() => {})");
+        assert!(matches!(
+            slow.wait(Duration::from_millis(10)),
+            Err(Error::Timeout)
+        ));
+        assert!(registry.evals.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn closing_the_connection_ends_its_evals() {
+        let registry: Arc<Registry> = Arc::default();
+        let (_socket, ctx) = crate::sender::detached(registry.clone(), 1, 7);
+        assert!(matches!(
+            eval(&ctx, "1").wait(Duration::from_secs(1)),
+            Err(Error::Closed)
+        ));
+        registry.connections.lock().unwrap().insert(1, 7);
+        let waiting = eval(&ctx, "1");
+        let (sender, called) = mpsc::channel();
+        eval(&ctx, "2").then(move |reply| sender.send(reply).unwrap());
+        close(&ctx);
+        assert!(matches!(
+            waiting.wait(Duration::from_secs(1)),
+            Err(Error::Closed)
+        ));
+        assert!(matches!(
+            called.recv_timeout(Duration::from_secs(1)).unwrap(),
+            Err(Error::Closed)
+        ));
+    }
+
+    #[test]
+    fn evals_move_with_a_resumed_session() {
+        let registry: Arc<Registry> = Arc::default();
+        registry.connections.lock().unwrap().insert(1, 7);
+        let (_socket, ctx) = crate::sender::detached(registry.clone(), 1, 7);
+        let (_new, resumed) = crate::sender::detached(registry.clone(), 2, 9);
+        let waiting = eval(&ctx, "1");
+        moved((1, 7), &resumed);
+        // the old socket is gone, the answer comes from the new one
+        reply(&ctx, waiting.id(), json!("old"), None);
+        close(&ctx);
+        reply(&resumed, waiting.id(), json!(1), None);
+        assert_eq!(waiting.wait(Duration::from_secs(1)).unwrap(), json!(1));
+    }
+
+    #[test]
+    fn dropped_handles_do_not_keep_their_eval() {
+        let registry: Arc<Registry> = Arc::default();
+        registry.connections.lock().unwrap().insert(1, 7);
+        let (_socket, ctx) = crate::sender::detached(registry.clone(), 1, 7);
+        drop(eval(&ctx, "1"));
+        assert!(registry.evals.lock().unwrap().is_empty());
+        let (sender, called) = mpsc::channel();
+        let later = eval(&ctx, "2");
+        let id = later.id();
+        later.then(move |reply| sender.send(reply).unwrap());
+        reply(&ctx, id, json!(2), None);
+        assert_eq!(
+            called
+                .recv_timeout(Duration::from_secs(1))
+                .unwrap()
+                .unwrap(),
+            json!(2)
+        );
+        assert!(registry.evals.lock().unwrap().is_empty());
+    }
+}
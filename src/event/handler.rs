@@ -1,90 +1,228 @@
+use razer_ws::CloseCode;
 use serde_json::Value;
 
-pub trait EventHandler: Send + Sync {
-    fn abort(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn afterprint(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn animationend(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn animationiteration(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn animationstart(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn beforeprint(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn beforeunload(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn blur(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn canplay(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn canplaythrough(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn change(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn click(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn contextmenu(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn copy(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn cut(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn dblclick(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn drag(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn dragend(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn dragenter(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn dragleave(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn dragover(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn dragstart(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn drop(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn durationchange(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn ended(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn error(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn focus(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn focusin(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn focusout(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn fullscreenchange(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn fullscreenerror(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn hashchange(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn input(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn invalid(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn keydown(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn keypress(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn keyup(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn load(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn loadeddata(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn loadedmetadata(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn loadstart(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn message(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn mousedown(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn mouseenter(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn mouseleave(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn mousemove(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn mouseover(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn mouseout(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn mouseup(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn mousewheel(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn offline(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn online(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn open(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn pagehide(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn pageshow(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn paste(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn pause(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn play(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn playing(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn popstate(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn progress(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn ratechange(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn resize(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn reset(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn scroll(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn search(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn seeked(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn seeking(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn select(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn show(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn stalled(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn storage(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn submit(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn suspend(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn timeupdate(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn toggle(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn touchcancel(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn touchend(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn touchmove(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn touchstart(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn transitionend(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn unload(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn volumechange(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn waiting(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn wheel(&self, _event: Value, _ctx: &razer_ws::Sender) {}
-    fn eval(&self, _event: Value, _ctx: &razer_ws::Sender) {}
+use crate::connections::ConnectionInfo;
+use crate::event::types::{
+    BrowserEvent, FocusEvent, GenericEvent, InputEvent, KeyboardEvent, MediaEvent, MouseEvent,
+    TouchEvent, WheelEvent,
+};
+
+/// Every method is called when the browser sends that event <br />
+/// the event is already parsed into the matching type from `razer::event::types`
+/// and the raw json can still be read from `event.raw`
+pub trait EventHandler: Send + 'static {
+    fn abort(&mut self, _event: GenericEvent, _ctx: &crate::Sender) {}
+    fn afterprint(&mut self, _event: GenericEvent, _ctx: &crate::Sender) {}
+    fn animationend(&mut self, _event: GenericEvent, _ctx: &crate::Sender) {}
+    fn animationiteration(&mut self, _event: GenericEvent, _ctx: &crate::Sender) {}
+    fn animationstart(&mut self, _event: GenericEvent, _ctx: &crate::Sender) {}
+    fn beforeprint(&mut self, _event: GenericEvent, _ctx: &crate::Sender) {}
+    fn beforeunload(&mut self, _event: GenericEvent, _ctx: &crate::Sender) {}
+    fn blur(&mut self, _event: FocusEvent, _ctx: &crate::Sender) {}
+    fn canplay(&mut self, _event: MediaEvent, _ctx: &crate::Sender) {}
+    fn canplaythrough(&mut self, _event: MediaEvent, _ctx: &crate::Sender) {}
+    fn change(&mut self, _event: InputEvent, _ctx: &crate::Sender) {}
+    fn click(&mut self, _event: MouseEvent, _ctx: &crate::Sender) {}
+    fn contextmenu(&mut self, _event: MouseEvent, _ctx: &crate::Sender) {}
+    fn copy(&mut self, _event: GenericEvent, _ctx: &crate::Sender) {}
+    fn cut(&mut self, _event: GenericEvent, _ctx: &crate::Sender) {}
+    fn dblclick(&mut self, _event: MouseEvent, _ctx: &crate::Sender) {}
+    fn drag(&mut self, _event: MouseEvent, _ctx: &crate::Sender) {}
+    fn dragend(&mut self, _event: MouseEvent, _ctx: &crate::Sender) {}
+    fn dragenter(&mut self, _event: MouseEvent, _ctx: &crate::Sender) {}
+    fn dragleave(&mut self, _event: MouseEvent, _ctx: &crate::Sender) {}
+    fn dragover(&mut self, _event: MouseEvent, _ctx: &crate::Sender) {}
+    fn dragstart(&mut self, _event: MouseEvent, _ctx: &crate::Sender) {}
+    fn drop(&mut self, _event: MouseEvent, _ctx: &crate::Sender) {}
+    fn durationchange(&mut self, _event: MediaEvent, _ctx: &crate::Sender) {}
+    fn ended(&mut self, _event: MediaEvent, _ctx: &crate::Sender) {}
+    fn error(&mut self, _event: GenericEvent, _ctx: &crate::Sender) {}
+    fn focus(&mut self, _event: FocusEvent, _ctx: &crate::Sender) {}
+    fn focusin(&mut self, _event: FocusEvent, _ctx: &crate::Sender) {}
+    fn focusout(&mut self, _event: FocusEvent, _ctx: &crate::Sender) {}
+    fn fullscreenchange(&mut self, _event: GenericEvent, _ctx: &crate::Sender) {}
+    fn fullscreenerror(&mut self, _event: GenericEvent, _ctx: &crate::Sender) {}
+    fn hashchange(&mut self, _event: GenericEvent, _ctx: &crate::Sender) {}
+    fn input(&mut self, _event: InputEvent, _ctx: &crate::Sender) {}
+    fn invalid(&mut self, _event: GenericEvent, _ctx: &crate::Sender) {}
+    fn keydown(&mut self, _event: KeyboardEvent, _ctx: &crate::Sender) {}
+    fn keypress(&mut self, _event: KeyboardEvent, _ctx: &crate::Sender) {}
+    fn keyup(&mut self, _event: KeyboardEvent, _ctx: &crate::Sender) {}
+    fn load(&mut self, _event: GenericEvent, _ctx: &crate::Sender) {}
+    fn loadeddata(&mut self, _event: MediaEvent, _ctx: &crate::Sender) {}
+    fn loadedmetadata(&mut self, _event: MediaEvent, _ctx: &crate::Sender) {}
+    fn loadstart(&mut self, _event: MediaEvent, _ctx: &crate::Sender) {}
+    fn message(&mut self, _event: GenericEvent, _ctx: &crate::Sender) {}
+    fn mousedown(&mut self, _event: MouseEvent, _ctx: &crate::Sender) {}
+    fn mouseenter(&mut self, _event: MouseEvent, _ctx: &crate::Sender) {}
+    fn mouseleave(&mut self, _event: MouseEvent, _ctx: &crate::Sender) {}
+    fn mousemove(&mut self, _event: MouseEvent, _ctx: &crate::Sender) {}
+    fn mouseover(&mut self, _event: MouseEvent, _ctx: &crate::Sender) {}
+    fn mouseout(&mut self, _event: MouseEvent, _ctx: &crate::Sender) {}
+    fn mouseup(&mut self, _event: MouseEvent, _ctx: &crate::Sender) {}
+    fn mousewheel(&mut self, _event: WheelEvent, _ctx: &crate::Sender) {}
+    fn offline(&mut self, _event: GenericEvent, _ctx: &crate::Sender) {}
+    fn online(&mut self, _event: GenericEvent, _ctx: &crate::Sender) {}
+    fn open(&mut self, _event: GenericEvent, _ctx: &crate::Sender) {}
+    fn pagehide(&mut self, _event: GenericEvent, _ctx: &crate::Sender) {}
+    fn pageshow(&mut self, _event: GenericEvent, _ctx: &crate::Sender) {}
+    fn paste(&mut self, _event: GenericEvent, _ctx: &crate::Sender) {}
+    fn pause(&mut self, _event: MediaEvent, _ctx: &crate::Sender) {}
+    fn play(&mut self, _event: MediaEvent, _ctx: &crate::Sender) {}
+    fn playing(&mut self, _event: MediaEvent, _ctx: &crate::Sender) {}
+    fn popstate(&mut self, _event: GenericEvent, _ctx: &crate::Sender) {}
+    fn progress(&mut self, _event: MediaEvent, _ctx: &crate::Sender) {}
+    fn ratechange(&mut self, _event: MediaEvent, _ctx: &crate::Sender) {}
+    fn resize(&mut self, _event: GenericEvent, _ctx: &crate::Sender) {}
+    fn reset(&mut self, _event: GenericEvent, _ctx: &crate::Sender) {}
+    fn scroll(&mut self, _event: GenericEvent, _ctx: &crate::Sender) {}
+    fn search(&mut self, _event: GenericEvent, _ctx: &crate::Sender) {}
+    fn seeked(&mut self, _event: MediaEvent, _ctx: &crate::Sender) {}
+    fn seeking(&mut self, _event: MediaEvent, _ctx: &crate::Sender) {}
+    fn select(&mut self, _event: GenericEvent, _ctx: &crate::Sender) {}
+    fn show(&mut self, _event: GenericEvent, _ctx: &crate::Sender) {}
+    fn stalled(&mut self, _event: MediaEvent, _ctx: &crate::Sender) {}
+    fn storage(&mut self, _event: GenericEvent, _ctx: &crate::Sender) {}
+    fn submit(&mut self, _event: GenericEvent, _ctx: &crate::Sender) {}
+    fn suspend(&mut self, _event: MediaEvent, _ctx: &crate::Sender) {}
+    fn timeupdate(&mut self, _event: MediaEvent, _ctx: &crate::Sender) {}
+    fn toggle(&mut self, _event: GenericEvent, _ctx: &crate::Sender) {}
+    fn touchcancel(&mut self, _event: TouchEvent, _ctx: &crate::Sender) {}
+    fn touchend(&mut self, _event: TouchEvent, _ctx: &crate::Sender) {}
+    fn touchmove(&mut self, _event: TouchEvent, _ctx: &crate::Sender) {}
+    fn touchstart(&mut self, _event: TouchEvent, _ctx: &crate::Sender) {}
+    fn transitionend(&mut self, _event: GenericEvent, _ctx: &crate::Sender) {}
+    fn unload(&mut self, _event: GenericEvent, _ctx: &crate::Sender) {}
+    fn volumechange(&mut self, _event: MediaEvent, _ctx: &crate::Sender) {}
+    fn waiting(&mut self, _event: MediaEvent, _ctx: &crate::Sender) {}
+    fn wheel(&mut self, _event: WheelEvent, _ctx: &crate::Sender) {}
+    fn eval(&mut self, _event: Value, _ctx: &crate::Sender) {}
+
+    /// This is called once the socket is open, before any event from that client
+    fn on_connect(&mut self, _info: ConnectionInfo, _ctx: &crate::Sender) {}
+
+    /// This is called after the socket was closed, so you can not send anything with `ctx` anymore
+    fn on_disconnect(&mut self, _code: CloseCode, _reason: &str, _ctx: &crate::Sender) {}
+
+    /// The dom events this handler uses, the website does not send any other event <br />
+    /// razer can not tell which methods you wrote, so the default is `None` and the website sends
+    /// every event, including every `mousemove` and `scroll`
+    /// ```
+    /// use razer::event::handler::EventHandler;
+    /// use razer::event::types::MouseEvent;
+    /// use razer::Sender;
+    ///
+    /// #[derive(Clone)]
+    /// pub struct Handler;
+    ///
+    /// impl EventHandler for Handler {
+    ///     fn click(&mut self, _event: MouseEvent, _ctx: &Sender) {}
+    ///
+    ///     fn subscriptions(&self) -> Option<Vec<String>> {
+    ///         Some(vec!["click".to_string()])
+    ///     }
+    /// }
+    /// ```
+    /// it is sent when the connection opens, use `razer::event::subscription::subscribe` to change
+    /// it after that
+    fn subscriptions(&self) -> Option<Vec<String>> {
+        None
+    }
+
+    /// This is called for events that are not a dom event razer knows about and were not
+    /// registered with `Listener::on`
+    fn on_unknown_event(&mut self, _name: &str, _event: Value, _ctx: &crate::Sender) {}
+
+    /// This is what the listener calls for every event, it will parse the event and call the
+    /// matching method
+    fn on_event(&mut self, name: &str, event: Value, ctx: &crate::Sender) {
+        match name {
+            "abort" => self.abort(BrowserEvent::from_value(event), ctx),
+            "afterprint" => self.afterprint(BrowserEvent::from_value(event), ctx),
+            "animationend" => self.animationend(BrowserEvent::from_value(event), ctx),
+            "animationiteration" => self.animationiteration(BrowserEvent::from_value(event), ctx),
+            "animationstart" => self.animationstart(BrowserEvent::from_value(event), ctx),
+            "beforeprint" => self.beforeprint(BrowserEvent::from_value(event), ctx),
+            "beforeunload" => self.beforeunload(BrowserEvent::from_value(event), ctx),
+            "blur" => self.blur(BrowserEvent::from_value(event), ctx),
+            "canplay" => self.canplay(BrowserEvent::from_value(event), ctx),
+            "canplaythrough" => self.canplaythrough(BrowserEvent::from_value(event), ctx),
+            "change" => self.change(BrowserEvent::from_value(event), ctx),
+            "click" => self.click(BrowserEvent::from_value(event), ctx),
+            "contextmenu" => self.contextmenu(BrowserEvent::from_value(event), ctx),
+            "copy" => self.copy(BrowserEvent::from_value(event), ctx),
+            "cut" => self.cut(BrowserEvent::from_value(event), ctx),
+            "dblclick" => self.dblclick(BrowserEvent::from_value(event), ctx),
+            "drag" => self.drag(BrowserEvent::from_value(event), ctx),
+            "dragend" => self.dragend(BrowserEvent::from_value(event), ctx),
+            "dragenter" => self.dragenter(BrowserEvent::from_value(event), ctx),
+            "dragleave" => self.dragleave(BrowserEvent::from_value(event), ctx),
+            "dragover" => self.dragover(BrowserEvent::from_value(event), ctx),
+            "dragstart" => self.dragstart(BrowserEvent::from_value(event), ctx),
+            "drop" => self.drop(BrowserEvent::from_value(event), ctx),
+            "durationchange" => self.durationchange(BrowserEvent::from_value(event), ctx),
+            "ended" => self.ended(BrowserEvent::from_value(event), ctx),
+            "error" => self.error(BrowserEvent::from_value(event), ctx),
+            "focus" => self.focus(BrowserEvent::from_value(event), ctx),
+            "focusin" => self.focusin(BrowserEvent::from_value(event), ctx),
+            "focusout" => self.focusout(BrowserEvent::from_value(event), ctx),
+            "fullscreenchange" => self.fullscreenchange(BrowserEvent::from_value(event), ctx),
+            "fullscreenerror" => self.fullscreenerror(BrowserEvent::from_value(event), ctx),
+            "hashchange" => self.hashchange(BrowserEvent::from_value(event), ctx),
+            "input" => self.input(BrowserEvent::from_value(event), ctx),
+            "invalid" => self.invalid(BrowserEvent::from_value(event), ctx),
+            "keydown" => self.keydown(BrowserEvent::from_value(event), ctx),
+            "keypress" => self.keypress(BrowserEvent::from_value(event), ctx),
+            "keyup" => self.keyup(BrowserEvent::from_value(event), ctx),
+            "load" => self.load(BrowserEvent::from_value(event), ctx),
+            "loadeddata" => self.loadeddata(BrowserEvent::from_value(event), ctx),
+            "loadedmetadata" => self.loadedmetadata(BrowserEvent::from_value(event), ctx),
+            "loadstart" => self.loadstart(BrowserEvent::from_value(event), ctx),
+            "message" => self.message(BrowserEvent::from_value(event), ctx),
+            "mousedown" => self.mousedown(BrowserEvent::from_value(event), ctx),
+            "mouseenter" => self.mouseenter(BrowserEvent::from_value(event), ctx),
+            "mouseleave" => self.mouseleave(BrowserEvent::from_value(event), ctx),
+            "mousemove" => self.mousemove(BrowserEvent::from_value(event), ctx),
+            "mouseover" => self.mouseover(BrowserEvent::from_value(event), ctx),
+            "mouseout" => self.mouseout(BrowserEvent::from_value(event), ctx),
+            "mouseup" => self.mouseup(BrowserEvent::from_value(event), ctx),
+            "mousewheel" => self.mousewheel(BrowserEvent::from_value(event), ctx),
+            "offline" => self.offline(BrowserEvent::from_value(event), ctx),
+            "online" => self.online(BrowserEvent::from_value(event), ctx),
+            "open" => self.open(BrowserEvent::from_value(event), ctx),
+            "pagehide" => self.pagehide(BrowserEvent::from_value(event), ctx),
+            "pageshow" => self.pageshow(BrowserEvent::from_value(event), ctx),
+            "paste" => self.paste(BrowserEvent::from_value(event), ctx),
+            "pause" => self.pause(BrowserEvent::from_value(event), ctx),
+            "play" => self.play(BrowserEvent::from_value(event), ctx),
+            "playing" => self.playing(BrowserEvent::from_value(event), ctx),
+            "popstate" => self.popstate(BrowserEvent::from_value(event), ctx),
+            "progress" => self.progress(BrowserEvent::from_value(event), ctx),
+            "ratechange" => self.ratechange(BrowserEvent::from_value(event), ctx),
+            "resize" => self.resize(BrowserEvent::from_value(event), ctx),
+            "reset" => self.reset(BrowserEvent::from_value(event), ctx),
+            "scroll" => self.scroll(BrowserEvent::from_value(event), ctx),
+            "search" => self.search(BrowserEvent::from_value(event), ctx),
+            "seeked" => self.seeked(BrowserEvent::from_value(event), ctx),
+            "seeking" => self.seeking(BrowserEvent::from_value(event), ctx),
+            "select" => self.select(BrowserEvent::from_value(event), ctx),
+            "show" => self.show(BrowserEvent::from_value(event), ctx),
+            "stalled" => self.stalled(BrowserEvent::from_value(event), ctx),
+            "storage" => self.storage(BrowserEvent::from_value(event), ctx),
+            "submit" => self.submit(BrowserEvent::from_value(event), ctx),
+            "suspend" => self.suspend(BrowserEvent::from_value(event), ctx),
+            "timeupdate" => self.timeupdate(BrowserEvent::from_value(event), ctx),
+            "toggle" => self.toggle(BrowserEvent::from_value(event), ctx),
+            "touchcancel" => self.touchcancel(BrowserEvent::from_value(event), ctx),
+            "touchend" => self.touchend(BrowserEvent::from_value(event), ctx),
+            "touchmove" => self.touchmove(BrowserEvent::from_value(event), ctx),
+            "touchstart" => self.touchstart(BrowserEvent::from_value(event), ctx),
+            "transitionend" => self.transitionend(BrowserEvent::from_value(event), ctx),
+            "unload" => self.unload(BrowserEvent::from_value(event), ctx),
+            "volumechange" => self.volumechange(BrowserEvent::from_value(event), ctx),
+            "waiting" => self.waiting(BrowserEvent::from_value(event), ctx),
+            "wheel" => self.wheel(BrowserEvent::from_value(event), ctx),
+            _ => self.on_unknown_event(name, event, ctx),
+        }
+    }
 }
@@ -1,2 +1,4 @@
 pub mod event_type;
 pub mod handler;
+pub mod subscription;
+pub mod types;
@@ -0,0 +1,48 @@
+use crate::error::Result;
+use crate::protocol::ServerMessage;
+use crate::send::send_message;
+
+/// This will tell the website to only send these dom events, every other event is not even
+/// turned into json on the website <br />
+/// razer sends `EventHandler::subscriptions` when a connection opens, use this to change them
+/// while the connection is open
+/// ```
+/// use razer::event::subscription::subscribe;
+/// use razer::Sender;
+/// fn start_drawing(ctx: &Sender) {
+///     // the mouse only matters while the user is drawing
+///     subscribe(ctx, &["load", "click", "mousemove", "mouseup"]).unwrap();
+/// }
+/// ```
+/// events from `razer.emit` and the names given to `Listener::on` are always sent
+pub fn subscribe<S: AsRef<str>>(ctx: &crate::Sender, events: &[S]) -> Result<()> {
+    send_subscriptions(
+        ctx,
+        Some(
+            events
+                .iter()
+                .map(|event| event.as_ref().to_string())
+                .collect(),
+        ),
+    )
+}
+
+/// This will tell the website to send every dom event again
+pub fn subscribe_all(ctx: &crate::Sender) -> Result<()> {
+    send_subscriptions(ctx, None)
+}
+
+pub(crate) fn send_subscriptions(ctx: &crate::Sender, events: Option<Vec<String>>) -> Result<()> {
+    let events = events.map(|mut events| {
+        events.extend(ctx.registry().custom.iter().cloned());
+        normalize(events)
+    });
+    send_message(ctx, &ServerMessage::Subscribe { events })
+}
+
+/// The events sorted and without any twice, so two sets can be compared
+pub(crate) fn normalize(mut events: Vec<String>) -> Vec<String> {
+    events.sort();
+    events.dedup();
+    events
+}
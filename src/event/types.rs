@@ -0,0 +1,454 @@
+use std::collections::HashMap;
+
+use serde::de::{DeserializeOwned, Deserializer};
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Every typed event can be built from the raw json the browser sent <br />
+/// if a field is missing or has the wrong type only that field falls back to its default, so a
+/// weird payload will never panic your handler and the other fields are still read <br />
+/// the untouched json is always kept in `raw` for the fields that are not modeled
+pub trait BrowserEvent: DeserializeOwned + Default {
+    fn set_raw(&mut self, raw: Value);
+
+    fn from_value(raw: Value) -> Self {
+        let mut event: Self = serde_json::from_value(raw.clone()).unwrap_or_default();
+        event.set_raw(raw);
+        event
+    }
+}
+
+// every field of the struct is read with `lenient`, so a missing or broken field only falls back
+// to its default, the fields that are skipped or read with their own function are left alone
+macro_rules! lenient {
+    ($(#[$meta:meta])* pub struct $name:ident { $($fields:tt)* }) => {
+        lenient!(@fields [$(#[$meta])* pub struct $name] [] $($fields)*);
+    };
+    (@fields [$($head:tt)*] [$($done:tt)*]) => {
+        $($head)* { $($done)* }
+    };
+    (@fields $head:tt [$($done:tt)*] #[serde(skip)] pub $field:ident: $ty:ty, $($rest:tt)*) => {
+        lenient!(@fields $head [$($done)* #[serde(skip)] pub $field: $ty,] $($rest)*);
+    };
+    (
+        @fields $head:tt [$($done:tt)*]
+        #[serde(deserialize_with = $with:literal)] pub $field:ident: $ty:ty, $($rest:tt)*
+    ) => {
+        lenient!(
+            @fields $head [$($done)* #[serde(deserialize_with = $with)] pub $field: $ty,] $($rest)*
+        );
+    };
+    (@fields $head:tt [$($done:tt)*] $(#[$attr:meta])* pub $field:ident: $ty:ty, $($rest:tt)*) => {
+        lenient!(
+            @fields $head
+            [$($done)* $(#[$attr])* #[serde(deserialize_with = "lenient")] pub $field: $ty,]
+            $($rest)*
+        );
+    };
+}
+
+lenient! {
+    /// The element that the event happened on
+    /// ```
+    /// use razer::event::types::MouseEvent;
+    /// fn click(event: MouseEvent) {
+    ///     if event.target.id == "button" {
+    ///         println!("the button was clicked");
+    ///     }
+    /// }
+    /// ```
+    #[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+    #[serde(default)]
+    pub struct Target {
+        pub id: String,
+        /// This is only set for elements that have a value like `<input>` or `<select>`
+        pub value: Option<String>,
+        /// The lowercase tag name like `button`
+        pub tag: String,
+        pub classes: Vec<String>,
+        pub attributes: HashMap<String, String>,
+        /// Every parent of the element starting from the closest one <br />
+        /// this is only sent for the `target` of the event
+        pub parents: Vec<Element>,
+    }
+}
+
+impl Target {
+    /// The target and then all of its parents
+    pub fn elements(&self) -> Vec<Element> {
+        let mut elements = Vec::with_capacity(self.parents.len() + 1);
+        elements.push(Element {
+            id: self.id.clone(),
+            tag: self.tag.clone(),
+            classes: self.classes.clone(),
+            attributes: self.attributes.clone(),
+        });
+        elements.extend(self.parents.iter().cloned());
+        elements
+    }
+}
+
+lenient! {
+    /// A parent of the target of an event
+    #[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+    #[serde(default)]
+    pub struct Element {
+        pub id: String,
+        pub tag: String,
+        pub classes: Vec<String>,
+        pub attributes: HashMap<String, String>,
+    }
+}
+
+lenient! {
+    /// The media element that the event happened on
+    #[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+    #[serde(default, rename_all = "camelCase")]
+    pub struct MediaTarget {
+        pub id: String,
+        pub current_time: f64,
+        pub duration: Option<f64>,
+        pub paused: bool,
+        pub muted: bool,
+        pub volume: f64,
+    }
+}
+
+lenient! {
+    /// This is used for every event that does not have a more specific type
+    #[derive(Debug, Clone, Default, Deserialize)]
+    #[serde(default, rename_all = "camelCase")]
+    pub struct GenericEvent {
+        #[serde(rename = "type")]
+        pub event_type: String,
+        pub target: Target,
+        pub time_stamp: f64,
+        #[serde(skip)]
+        pub raw: Value,
+    }
+}
+
+lenient! {
+    /// click, dblclick, contextmenu, drag and every mouse event
+    #[derive(Debug, Clone, Default, Deserialize)]
+    #[serde(default, rename_all = "camelCase")]
+    pub struct MouseEvent {
+        #[serde(rename = "type")]
+        pub event_type: String,
+        pub target: Target,
+        pub time_stamp: f64,
+        pub client_x: f64,
+        pub client_y: f64,
+        pub page_x: f64,
+        pub page_y: f64,
+        pub screen_x: f64,
+        pub screen_y: f64,
+        pub offset_x: f64,
+        pub offset_y: f64,
+        pub movement_x: f64,
+        pub movement_y: f64,
+        pub button: i16,
+        pub buttons: u16,
+        pub alt_key: bool,
+        pub ctrl_key: bool,
+        pub shift_key: bool,
+        pub meta_key: bool,
+        #[serde(skip)]
+        pub raw: Value,
+    }
+}
+
+lenient! {
+    /// keydown, keypress and keyup
+    /// ```
+    /// use razer::event::types::KeyboardEvent;
+    /// fn keydown(event: KeyboardEvent) {
+    ///     if event.key == "Enter" && event.target.id == "input" {
+    ///         println!("{:?}", event.target.value);
+    ///     }
+    /// }
+    /// ```
+    #[derive(Debug, Clone, Default, Deserialize)]
+    #[serde(default, rename_all = "camelCase")]
+    pub struct KeyboardEvent {
+        #[serde(rename = "type")]
+        pub event_type: String,
+        pub target: Target,
+        pub time_stamp: f64,
+        pub key: String,
+        pub code: String,
+        pub location: u32,
+        pub repeat: bool,
+        pub is_composing: bool,
+        pub alt_key: bool,
+        pub ctrl_key: bool,
+        pub shift_key: bool,
+        pub meta_key: bool,
+        #[serde(skip)]
+        pub raw: Value,
+    }
+}
+
+lenient! {
+    /// wheel and mousewheel
+    #[derive(Debug, Clone, Default, Deserialize)]
+    #[serde(default, rename_all = "camelCase")]
+    pub struct WheelEvent {
+        #[serde(rename = "type")]
+        pub event_type: String,
+        pub target: Target,
+        pub time_stamp: f64,
+        pub delta_x: f64,
+        pub delta_y: f64,
+        pub delta_z: f64,
+        pub delta_mode: u32,
+        pub client_x: f64,
+        pub client_y: f64,
+        pub alt_key: bool,
+        pub ctrl_key: bool,
+        pub shift_key: bool,
+        pub meta_key: bool,
+        #[serde(skip)]
+        pub raw: Value,
+    }
+}
+
+lenient! {
+    /// A single finger on the screen
+    #[derive(Debug, Clone, Default, Deserialize)]
+    #[serde(default, rename_all = "camelCase")]
+    pub struct Touch {
+        pub identifier: i64,
+        pub target: Target,
+        pub client_x: f64,
+        pub client_y: f64,
+        pub page_x: f64,
+        pub page_y: f64,
+        pub screen_x: f64,
+        pub screen_y: f64,
+        pub radius_x: f64,
+        pub radius_y: f64,
+        pub force: f64,
+    }
+}
+
+lenient! {
+    /// touchstart, touchmove, touchend and touchcancel
+    #[derive(Debug, Clone, Default, Deserialize)]
+    #[serde(default, rename_all = "camelCase")]
+    pub struct TouchEvent {
+        #[serde(rename = "type")]
+        pub event_type: String,
+        pub target: Target,
+        pub time_stamp: f64,
+        #[serde(deserialize_with = "touch_list")]
+        pub touches: Vec<Touch>,
+        #[serde(deserialize_with = "touch_list")]
+        pub target_touches: Vec<Touch>,
+        #[serde(deserialize_with = "touch_list")]
+        pub changed_touches: Vec<Touch>,
+        pub alt_key: bool,
+        pub ctrl_key: bool,
+        pub shift_key: bool,
+        pub meta_key: bool,
+        #[serde(skip)]
+        pub raw: Value,
+    }
+}
+
+lenient! {
+    /// input and change
+    #[derive(Debug, Clone, Default, Deserialize)]
+    #[serde(default, rename_all = "camelCase")]
+    pub struct InputEvent {
+        #[serde(rename = "type")]
+        pub event_type: String,
+        pub target: Target,
+        pub time_stamp: f64,
+        pub data: Option<String>,
+        pub input_type: String,
+        pub is_composing: bool,
+        #[serde(skip)]
+        pub raw: Value,
+    }
+}
+
+lenient! {
+    /// focus, blur, focusin and focusout
+    #[derive(Debug, Clone, Default, Deserialize)]
+    #[serde(default, rename_all = "camelCase")]
+    pub struct FocusEvent {
+        #[serde(rename = "type")]
+        pub event_type: String,
+        pub target: Target,
+        pub time_stamp: f64,
+        pub related_target: Option<Target>,
+        #[serde(skip)]
+        pub raw: Value,
+    }
+}
+
+lenient! {
+    /// Every event that comes from a `<audio>` or `<video>` element like play, pause or timeupdate
+    #[derive(Debug, Clone, Default, Deserialize)]
+    #[serde(default, rename_all = "camelCase")]
+    pub struct MediaEvent {
+        #[serde(rename = "type")]
+        pub event_type: String,
+        pub target: MediaTarget,
+        pub time_stamp: f64,
+        #[serde(skip)]
+        pub raw: Value,
+    }
+}
+
+// so a handler can ask for the raw json
+impl BrowserEvent for Value {
+    fn set_raw(&mut self, raw: Value) {
+        *self = raw;
+    }
+
+    fn from_value(raw: Value) -> Self {
+        raw
+    }
+}
+
+impl BrowserEvent for GenericEvent {
+    fn set_raw(&mut self, raw: Value) {
+        self.raw = raw;
+    }
+}
+
+impl BrowserEvent for MouseEvent {
+    fn set_raw(&mut self, raw: Value) {
+        self.raw = raw;
+    }
+}
+
+impl BrowserEvent for KeyboardEvent {
+    fn set_raw(&mut self, raw: Value) {
+        self.raw = raw;
+    }
+}
+
+impl BrowserEvent for WheelEvent {
+    fn set_raw(&mut self, raw: Value) {
+        self.raw = raw;
+    }
+}
+
+impl BrowserEvent for TouchEvent {
+    fn set_raw(&mut self, raw: Value) {
+        self.raw = raw;
+    }
+}
+
+impl BrowserEvent for InputEvent {
+    fn set_raw(&mut self, raw: Value) {
+        self.raw = raw;
+    }
+}
+
+impl BrowserEvent for FocusEvent {
+    fn set_raw(&mut self, raw: Value) {
+        self.raw = raw;
+    }
+}
+
+impl BrowserEvent for MediaEvent {
+    fn set_raw(&mut self, raw: Value) {
+        self.raw = raw;
+    }
+}
+
+// a field that can not be read is left at its default instead of failing the whole event, like
+// `"target": "Window"` for events on the window or a `currentTime` of `null`
+fn lenient<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned + Default,
+{
+    Ok(serde_json::from_value(Value::deserialize(deserializer)?).unwrap_or_default())
+}
+
+// the client sends a TouchList as `{"0": {..}, "1": {..}, "length": 2}`
+fn touch_list<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Touch>, D::Error> {
+    let value = Value::deserialize(deserializer)?;
+    let mut touches: Vec<(usize, Touch)> = match value {
+        Value::Array(items) => items.into_iter().enumerate().collect::<Vec<_>>(),
+        Value::Object(items) => items
+            .into_iter()
+            .filter_map(|(key, item)| key.parse::<usize>().ok().map(|i| (i, item)))
+            .collect(),
+        _ => Vec::new(),
+    }
+    .into_iter()
+    .map(|(i, item)| (i, serde_json::from_value(item).unwrap_or_default()))
+    .collect();
+    touches.sort_by_key(|(i, _)| *i);
+    Ok(touches.into_iter().map(|(_, touch)| touch).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn events_on_the_window_keep_their_fields() {
+        let raw = json!({"type": "resize", "timeStamp": 12.5, "target": "Window"});
+        let event = GenericEvent::from_value(raw.clone());
+        assert_eq!(event.event_type, "resize");
+        assert_eq!(event.time_stamp, 12.5);
+        assert_eq!(event.target, Target::default());
+        assert_eq!(event.raw, raw);
+    }
+
+    #[test]
+    fn one_bad_field_does_not_lose_the_others() {
+        let event = MediaEvent::from_value(json!({
+            "type": "timeupdate",
+            "timeStamp": 3.0,
+            "target": {"id": "video", "currentTime": null, "duration": 60.0, "paused": "no"},
+        }));
+        assert_eq!(event.event_type, "timeupdate");
+        assert_eq!(event.target.id, "video");
+        assert_eq!(event.target.current_time, 0.0);
+        assert_eq!(event.target.duration, Some(60.0));
+        assert!(!event.target.paused);
+        let event = MouseEvent::from_value(json!({
+            "type": "click",
+            "clientX": "10",
+            "clientY": 20,
+            "button": 1,
+            "target": {"id": "save", "classes": "big", "tag": "button"},
+        }));
+        assert_eq!(event.client_x, 0.0);
+        assert_eq!(event.client_y, 20.0);
+        assert_eq!(event.button, 1);
+        assert_eq!(event.target.id, "save");
+        assert_eq!(event.target.tag, "button");
+        assert!(event.target.classes.is_empty());
+    }
+
+    #[test]
+    fn touch_lists_are_read_in_order() {
+        let event = TouchEvent::from_value(json!({
+            "type": "touchstart",
+            "touches": {"1": {"identifier": 8}, "0": {"identifier": 7}, "length": 2},
+            "changedTouches": [{"identifier": 7, "clientX": null}],
+        }));
+        let ids: Vec<i64> = event.touches.iter().map(|touch| touch.identifier).collect();
+        assert_eq!(ids, vec![7, 8]);
+        assert_eq!(event.changed_touches.len(), 1);
+        assert!(event.target_touches.is_empty());
+    }
+
+    #[test]
+    fn anything_that_is_not_an_object_is_the_default_event() {
+        let event = KeyboardEvent::from_value(Value::Null);
+        assert_eq!(event.key, "");
+        assert_eq!(event.raw, Value::Null);
+    }
+}
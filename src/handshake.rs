@@ -0,0 +1,174 @@
+use std::fmt;
+use std::sync::Arc;
+
+use razer_ws::Request;
+
+use crate::connections::parse_cookies;
+
+/// Where the csrf token is in the request that opens the websocket, see `Listener::csrf`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Csrf {
+    /// A query parameter of the websocket address, the `/razer.js` that razer serves adds it from
+    /// `<meta name="razer-csrf" content="...">` if the page has one
+    Query(String),
+    /// A cookie <br />
+    /// browsers send cookies with a websocket that another website opens too, so this only stops
+    /// cross-site websocket hijacking if the cookie is `SameSite=Strict` or `SameSite=Lax`, or if
+    /// `Listener::allowed_origins` is set as well, otherwise use `Csrf::Query`
+    Cookie(String),
+}
+
+type CsrfCheck = Arc<dyn Fn(&str) -> bool + Send + Sync>;
+
+/// What a request has to have before it can open a websocket
+#[derive(Clone, Default)]
+pub(crate) struct Guard {
+    // lowercase and without a `/` at the end
+    pub(crate) origins: Option<Vec<String>>,
+    pub(crate) csrf: Option<(Csrf, CsrfCheck)>,
+}
+
+impl Guard {
+    /// Why the request can not open a websocket, if it can not
+    pub(crate) fn check(&self, req: &Request) -> Result<(), &'static str> {
+        if let Some(origins) = &self.origins {
+            // browsers always send the origin, so a request without one is not let through
+            let origin = req.origin().ok().flatten().map(normalize_origin);
+            if !origin.is_some_and(|origin| origins.contains(&origin)) {
+                return Err("the origin is not allowed");
+            }
+        }
+        if let Some((csrf, check)) = &self.csrf {
+            let token = match csrf {
+                Csrf::Query(name) => query(req.resource(), name),
+                Csrf::Cookie(name) => req
+                    .header("cookie")
+                    .and_then(|cookies| {
+                        parse_cookies(&String::from_utf8_lossy(cookies)).remove(name)
+                    })
+                    .map(|token| token.trim_matches('"').to_string()),
+            };
+            match token {
+                Some(token) if check(&token) => {}
+                Some(_) => return Err("the csrf token is not valid"),
+                None => return Err("there is no csrf token"),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Guard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Guard")
+            .field("origins", &self.origins)
+            .field("csrf", &self.csrf.as_ref().map(|(csrf, _)| csrf))
+            .finish()
+    }
+}
+
+pub(crate) fn normalize_origin(origin: &str) -> String {
+    origin.trim().trim_end_matches('/').to_ascii_lowercase()
+}
+
+/// The value of a query parameter of `resource`
+pub(crate) fn query(resource: &str, name: &str) -> Option<String> {
+    let (_, query) = resource.split_once('?')?;
+    let query = query.split('#').next().unwrap_or("");
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| decode(key) == name)
+        .map(|(_, value)| decode(value))
+}
+
+// undoes `encodeURIComponent`, anything that is not valid is kept like it is
+fn decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = match bytes.get(i + 1..i + 3) {
+            Some(&[high, low]) if bytes[i] == b'%' => hex(high).zip(hex(low)),
+            _ => None,
+        };
+        match (escaped, bytes[i]) {
+            (Some((high, low)), _) => {
+                decoded.push(high << 4 | low);
+                i += 3;
+                continue;
+            }
+            (None, b'+') => decoded.push(b' '),
+            (None, byte) => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn hex(digit: u8) -> Option<u8> {
+    (digit as char).to_digit(16).map(|digit| digit as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(resource: &str, headers: &str) -> Request {
+        let raw = format!(
+            "GET {} HTTP/1.1\r\nHost: example.com\r\nUpgrade: websocket\r\n{}\r\n",
+            resource, headers
+        );
+        Request::parse(raw.as_bytes()).unwrap().unwrap()
+    }
+
+    #[test]
+    fn only_allowed_origins_can_connect() {
+        let guard = Guard {
+            origins: Some(vec![normalize_origin("https://Example.com/")]),
+            ..Guard::default()
+        };
+        assert!(guard
+            .check(&request("/", "Origin: https://example.com\r\n"))
+            .is_ok());
+        assert!(guard
+            .check(&request("/", "Origin: https://evil.com\r\n"))
+            .is_err());
+        assert!(guard
+            .check(&request("/", "Origin: http://example.com\r\n"))
+            .is_err());
+        assert!(guard.check(&request("/", "")).is_err());
+        assert!(Guard::default().check(&request("/", "")).is_ok());
+    }
+
+    #[test]
+    fn the_csrf_token_has_to_be_valid() {
+        let check: CsrfCheck = Arc::new(|token| token == "a b/c");
+        let query = Guard {
+            csrf: Some((Csrf::Query("csrf".to_string()), check.clone())),
+            ..Guard::default()
+        };
+        assert!(query.check(&request("/?x=1&csrf=a%20b%2Fc", "")).is_ok());
+        assert!(query.check(&request("/?csrf=wrong", "")).is_err());
+        assert!(query.check(&request("/", "")).is_err());
+        let cookie = Guard {
+            csrf: Some((Csrf::Cookie("csrf".to_string()), check)),
+            ..Guard::default()
+        };
+        assert!(cookie
+            .check(&request("/", "Cookie: theme=dark; csrf=\"a b/c\"\r\n"))
+            .is_ok());
+        assert!(cookie
+            .check(&request("/?csrf=a%20b%2Fc", "Cookie: theme=dark\r\n"))
+            .is_err());
+    }
+
+    #[test]
+    fn query_values_are_decoded() {
+        assert_eq!(query("/?a=1%3D2&b=x+y", "a"), Some("1=2".to_string()));
+        assert_eq!(query("/?a=1%3D2&b=x+y", "b"), Some("x y".to_string()));
+        assert_eq!(query("/?a=100%", "a"), Some("100%".to_string()));
+        assert_eq!(query("/?a=%zz", "a"), Some("%zz".to_string()));
+        assert_eq!(query("/", "a"), None);
+    }
+}
@@ -0,0 +1,291 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use log::debug;
+use razer_ws::{Request, Response};
+
+// the script is built into razer so it is always the version that matches the server
+const SCRIPT: &str = include_str!("../js/razer.js");
+const SCRIPT_HOST: &str = "const RAZER_HOST = window.location.hostname + \":2794\";";
+const SCRIPT_PATH: &str = "const RAZER_PATH = \"/\";";
+const SCRIPT_CSRF: &str = "const RAZER_CSRF = null;";
+
+/// Where the script is served from
+pub(crate) const SCRIPT_ROUTE: &str = "/razer.js";
+
+#[derive(Clone, Debug)]
+pub(crate) enum Asset {
+    Bytes {
+        content_type: String,
+        body: Arc<Vec<u8>>,
+    },
+    // read on every request so a change to the file shows up without restarting the server
+    File(PathBuf),
+    Directory(PathBuf),
+}
+
+/// What the server answers to requests that are not a websocket
+#[derive(Clone, Debug)]
+pub(crate) struct Http {
+    pub(crate) routes: HashMap<String, Asset>,
+    pub(crate) public_address: Option<String>,
+    pub(crate) socket_path: String,
+    // the query parameter the script puts the csrf token in
+    pub(crate) csrf_param: Option<String>,
+}
+
+impl Default for Http {
+    fn default() -> Self {
+        Http {
+            routes: HashMap::new(),
+            public_address: None,
+            socket_path: "/".to_string(),
+            csrf_param: None,
+        }
+    }
+}
+
+impl Http {
+    pub(crate) fn respond(&self, req: &Request) -> Response {
+        let head = req.method() == "HEAD";
+        if req.method() != "GET" && !head {
+            let mut response = status(405, "Method Not Allowed");
+            add_header(&mut response, "Allow", "GET, HEAD");
+            return response;
+        }
+        let route = req.resource().split(['?', '#']).next().unwrap_or("/");
+        let mut response = match self.find(route, req) {
+            Ok((content_type, body)) => {
+                let mut response = Response::new(200, "OK", body);
+                add_header(&mut response, "Content-Type", &content_type);
+                response
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => status(404, "Not Found"),
+            Err(err) => {
+                debug!("could not serve {}: {}", route, err);
+                status(500, "Internal Server Error")
+            }
+        };
+        if head {
+            // the length of the body is kept so the client knows how big it would be
+            let mut empty = Response::new(response.status(), response.reason(), Vec::new());
+            *empty.headers_mut() = response.headers().clone();
+            response = empty;
+        }
+        // ws-rs closes the socket after anything that is not a websocket
+        add_header(&mut response, "Connection", "close");
+        response
+    }
+
+    fn find(&self, route: &str, req: &Request) -> io::Result<(String, Vec<u8>)> {
+        if let Some(asset) = self.routes.get(route) {
+            return match asset {
+                Asset::Bytes { content_type, body } => Ok((content_type.clone(), body.to_vec())),
+                Asset::File(path) => Ok((content_type(path).to_string(), fs::read(path)?)),
+                Asset::Directory(dir) => read_directory(dir, ""),
+            };
+        }
+        if route == SCRIPT_ROUTE {
+            let host = req
+                .header("host")
+                .map(|host| String::from_utf8_lossy(host).into_owned());
+            return Ok((
+                "text/javascript; charset=utf-8".to_string(),
+                self.script(host.as_deref()).into_bytes(),
+            ));
+        }
+        // the longest directory that the route is in
+        let directory = self
+            .routes
+            .iter()
+            .filter_map(|(prefix, asset)| match asset {
+                Asset::Directory(dir) => Some((prefix, dir, inside(route, prefix)?)),
+                _ => None,
+            })
+            .max_by_key(|(prefix, _, _)| prefix.len());
+        match directory {
+            Some((_, dir, rest)) => read_directory(dir, rest),
+            None => Err(io::ErrorKind::NotFound.into()),
+        }
+    }
+
+    // the script with the address of the websocket filled in, the host is the one the page used
+    // to get the script unless `Listener::public_address` was set
+    pub(crate) fn script(&self, host: Option<&str>) -> String {
+        let mut script = SCRIPT.to_string();
+        if let Some(host) = self.public_address.as_deref().or(host) {
+            script = script.replacen(
+                SCRIPT_HOST,
+                &format!("const RAZER_HOST = {};", quote(host)),
+                1,
+            );
+        }
+        if let Some(param) = &self.csrf_param {
+            script = script.replacen(
+                SCRIPT_CSRF,
+                &format!("const RAZER_CSRF = {};", quote(param)),
+                1,
+            );
+        }
+        script.replacen(
+            SCRIPT_PATH,
+            &format!("const RAZER_PATH = {};", quote(&self.socket_path)),
+            1,
+        )
+    }
+}
+
+fn quote(value: &str) -> String {
+    serde_json::to_string(value).expect("a string can always be turned into json")
+}
+
+pub(crate) fn status(status: u16, reason: &str) -> Response {
+    let mut response = Response::new(status, reason, reason.as_bytes().to_vec());
+    add_header(&mut response, "Content-Type", "text/plain; charset=utf-8");
+    response
+}
+
+fn add_header(response: &mut Response, name: &str, value: &str) {
+    response
+        .headers_mut()
+        .push((name.to_string(), value.as_bytes().to_vec()));
+}
+
+// what is left of the route after the directory, if the route is in it
+fn inside<'a>(route: &'a str, prefix: &str) -> Option<&'a str> {
+    let rest = route.strip_prefix(prefix.trim_end_matches('/'))?;
+    if rest.is_empty() || rest.starts_with('/') {
+        Some(rest.trim_start_matches('/'))
+    } else {
+        None
+    }
+}
+
+fn read_directory(dir: &Path, rest: &str) -> io::Result<(String, Vec<u8>)> {
+    let mut path = dir.to_path_buf();
+    for part in rest.split('/').filter(|part| !part.is_empty()) {
+        // nothing outside of the directory can be reached
+        if part == "." || part == ".." || part.contains('\\') || part.contains(':') {
+            return Err(io::ErrorKind::NotFound.into());
+        }
+        path.push(part);
+    }
+    if path.is_dir() {
+        path.push("index.html");
+    }
+    let body = fs::read(&path)?;
+    Ok((content_type(&path).to_string(), body))
+}
+
+/// The content type for a file from its extension
+pub(crate) fn content_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+    match extension.as_deref() {
+        Some("html" | "htm") => "text/html; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("js" | "mjs") => "text/javascript; charset=utf-8",
+        Some("json" | "map") => "application/json",
+        Some("txt") => "text/plain; charset=utf-8",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("ico") => "image/x-icon",
+        Some("wasm") => "application/wasm",
+        Some("woff") => "font/woff",
+        Some("woff2") => "font/woff2",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get(http: &Http, method: &str, resource: &str) -> Response {
+        let raw = format!(
+            "{} {} HTTP/1.1\r\nHost: example.com:8080\r\n\r\n",
+            method, resource
+        );
+        let req = Request::parse(raw.as_bytes()).unwrap().unwrap();
+        http.respond(&req)
+    }
+
+    #[test]
+    fn the_script_connects_to_the_server_it_came_from() {
+        let http = Http::default();
+        let response = get(&http, "GET", "/razer.js?v=2");
+        assert_eq!(response.status(), 200);
+        let script = String::from_utf8(response.body().to_vec()).unwrap();
+        assert!(script.contains("const RAZER_HOST = \"example.com:8080\";"));
+        assert!(script.contains("const RAZER_PATH = \"/\";"));
+        assert!(!script.contains(SCRIPT_HOST));
+    }
+
+    #[test]
+    fn the_script_can_use_another_address() {
+        let http = Http {
+            public_address: Some("live.example.com".to_string()),
+            socket_path: "/socket\"".to_string(),
+            csrf_param: Some("csrf".to_string()),
+            ..Http::default()
+        };
+        let script = http.script(Some("example.com:8080"));
+        assert!(script.contains("const RAZER_HOST = \"live.example.com\";"));
+        assert!(script.contains("const RAZER_PATH = \"/socket\\\"\";"));
+        assert!(script.contains("const RAZER_CSRF = \"csrf\";"));
+    }
+
+    #[test]
+    fn routes_are_served() {
+        let mut http = Http::default();
+        http.routes.insert(
+            "/".to_string(),
+            Asset::Bytes {
+                content_type: "text/html; charset=utf-8".to_string(),
+                body: Arc::new(b"<h1>hi</h1>".to_vec()),
+            },
+        );
+        let response = get(&http, "GET", "/");
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.body(), b"<h1>hi</h1>");
+        assert_eq!(get(&http, "GET", "/missing").status(), 404);
+        assert_eq!(get(&http, "POST", "/").status(), 405);
+        let head = get(&http, "HEAD", "/");
+        assert_eq!(head.status(), 200);
+        assert!(head.body().is_empty());
+    }
+
+    #[test]
+    fn directories_can_not_be_left() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("js");
+        let mut http = Http::default();
+        http.routes
+            .insert("/static".to_string(), Asset::Directory(dir));
+        let response = get(&http, "GET", "/static/razer.js");
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.body(), SCRIPT.as_bytes());
+        assert_eq!(get(&http, "GET", "/static/../Cargo.toml").status(), 404);
+        assert_eq!(get(&http, "GET", "/staticrazer.js").status(), 404);
+    }
+
+    #[test]
+    fn content_types_come_from_the_extension() {
+        assert_eq!(
+            content_type(Path::new("a/index.HTML")),
+            "text/html; charset=utf-8"
+        );
+        assert_eq!(content_type(Path::new("logo.svg")), "image/svg+xml");
+        assert_eq!(
+            content_type(Path::new("README")),
+            "application/octet-stream"
+        );
+    }
+}
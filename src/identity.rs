@@ -0,0 +1,157 @@
+use std::fmt;
+
+use razer_ws::util::Token;
+use serde_json::Value;
+
+use crate::error::Result;
+use crate::event::event_type::Event;
+use crate::protocol::ServerMessage;
+use crate::registry::Registry;
+use crate::send::{multicast_message, targets};
+
+/// Who opened a connection, this is made by the hook given to `Listener::authenticate` before the
+/// websocket is opened
+/// ```no_run
+/// use razer::identity::{Identity, Reject};
+/// use razer::listener::Listener;
+/// # use razer::event::handler::EventHandler;
+/// # #[derive(Clone)]
+/// # pub struct Handler;
+/// # impl EventHandler for Handler {}
+/// # fn user_of_session(session: &[u8]) -> Option<String> { None }
+///
+/// Listener::new()
+///     .authenticate(|req| {
+///         let session = req.header("x-session").ok_or_else(Reject::unauthorized)?;
+///         let user = user_of_session(session).ok_or_else(Reject::forbidden)?;
+///         Ok(Identity::new(&user))
+///     })
+///     .start(Handler)
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Identity {
+    /// Who the user is, like their id, `send_identity` sends to every connection with this id
+    pub id: String,
+    /// Anything else about the user, like their name or what they are allowed to do
+    pub data: Value,
+}
+
+impl Identity {
+    pub fn new(id: &str) -> Identity {
+        Identity {
+            id: id.to_string(),
+            data: Value::Null,
+        }
+    }
+
+    /// The same identity with `data` added
+    pub fn with_data(self, data: Value) -> Identity {
+        Identity { data, ..self }
+    }
+}
+
+/// Why `Listener::authenticate` did not let a request open a websocket, it is sent back as the
+/// status of the response
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reject {
+    pub status: u16,
+    pub reason: String,
+}
+
+impl Reject {
+    pub fn new(status: u16, reason: &str) -> Reject {
+        Reject {
+            status,
+            reason: reason.to_string(),
+        }
+    }
+
+    /// `401 Unauthorized`, for requests that do not say who they are
+    pub fn unauthorized() -> Reject {
+        Reject::new(401, "Unauthorized")
+    }
+
+    /// `403 Forbidden`, for requests from someone that is not allowed in
+    pub fn forbidden() -> Reject {
+        Reject::new(403, "Forbidden")
+    }
+}
+
+impl fmt::Display for Reject {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.status, self.reason)
+    }
+}
+
+/// Who opened the connection, this is `None` if the `Listener` has no `authenticate` hook
+/// ```
+/// use razer::event::types::MouseEvent;
+/// use razer::identity::identity;
+/// use razer::Sender;
+/// fn click(_event: MouseEvent, ctx: &Sender) {
+///     if let Some(user) = identity(ctx) {
+///         println!("{} clicked", user.id);
+///     }
+/// }
+/// ```
+pub fn identity(ctx: &crate::Sender) -> Option<Identity> {
+    let (token, connection_id) = ctx.socket();
+    ctx.registry()
+        .identities
+        .lock()
+        .unwrap()
+        .get(&token)
+        .filter(|(open, _)| *open == connection_id)
+        .map(|(_, identity)| identity.clone())
+}
+
+/// Every connection of the identity with this id, like all the tabs a user has open
+pub fn connections_of(ctx: &crate::Sender, id: &str) -> Vec<Token> {
+    open(ctx.registry(), id)
+        .into_iter()
+        .map(|(token, _)| token)
+        .collect()
+}
+
+/// This will send data to every connection of the identity with this id
+/// ```
+/// use razer::event::event_type::Event::JS;
+/// use razer::identity::send_identity;
+/// use razer::Sender;
+/// fn order_shipped(ctx: &Sender, user: &str) {
+///     send_identity(ctx, user, JS, "alert(\"your order is on its way\")").unwrap();
+/// }
+/// ```
+pub fn send_identity(client: &crate::Sender, id: &str, event: Event, data: &str) -> Result<()> {
+    send_identity_message(client, id, &ServerMessage::new(event, data))
+}
+
+/// The same as `send_identity` but for any `ServerMessage`
+pub fn send_identity_message(
+    client: &crate::Sender,
+    id: &str,
+    message: &ServerMessage,
+) -> Result<()> {
+    let targets = targets(
+        client,
+        |registry| open(registry, id),
+        |away| {
+            away.identity
+                .as_ref()
+                .is_some_and(|identity| identity.id == id)
+        },
+    );
+    multicast_message(client, targets, message)
+}
+
+fn open(registry: &Registry, id: &str) -> Vec<(Token, u32)> {
+    registry
+        .identities
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(_, (_, identity))| identity.id == id)
+        .map(|(token, (connection_id, _))| (Token::from(*token), *connection_id))
+        .collect()
+}
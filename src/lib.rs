@@ -2,13 +2,13 @@
 
 //! Razer is serverside way to control your website with rust
 //! # Example
-//! ```
-//!use razer::event::event_type::Event::JS;
+//! ```no_run
+//! use razer::event::event_type::Event::JS;
 //! use razer::event::handler::EventHandler;
+//! use razer::event::types::GenericEvent;
 //! use razer::listener::Listener;
 //! use razer::send::send;
 //! use razer::Sender;
-//! use razer::Value;
 //!
 //! #[derive(Copy)]
 //! pub struct Handler;
@@ -20,35 +20,51 @@
 //! }
 //!
 //! impl EventHandler for Handler {
-//!     fn load(&self, _event: Value, ctx: &Sender) {
+//!     fn load(&mut self, _event: GenericEvent, ctx: &Sender) {
 //!         send(ctx, JS, "alert(\"Hello\")").unwrap();
 //!     }
 //! }
 //!
-//! fn main() {
-//!     Listener::new().start(Handler);
-//! }
+//! Listener::new()
+//!     .index("<!DOCTYPE html><script src=\"/razer.js\"></script>")
+//!     .start(Handler)
+//!     .unwrap();
 //! ```
 //! this will alert "Hello" when ever someone goes onto your site <br />
-//! To add the script all you will have to do is add this to your html
+//! The server serves the script on `/razer.js`, to add it to your own html all you will have to do
+//! is add this
 //!
 //! ```html
-//! <script src="https://cdn.jsdelivr.net/gh/AMTitan/razer@(your version)/js/razer.min.js"></script>
-//! ```
-//! 
-//! an example would be
-//! 
-//! ```html
-//! <script src="https://cdn.jsdelivr.net/gh/AMTitan/razer@0.1.4/js/razer.min.js"></script>
+//! <script src="/razer.js"></script>
 //! ```
 
-pub use razer_ws::Sender;
+pub use sender::Sender;
 pub use razer_ws::util::Token;
+pub use razer_ws::CloseCode;
+pub use razer_ws::{Request, Settings};
 pub use serde_json::Value;
+pub use error::{Error, Result};
 
-use crate::event::handler::EventHandler;
-
+pub mod component;
 pub mod connections;
+pub mod dom;
+pub mod error;
+pub mod eval;
 pub mod event;
+pub mod handshake;
+mod http;
+pub mod identity;
+pub mod limit;
 pub mod listener;
+pub mod pool;
+pub mod protocol;
+mod registry;
+mod resume;
+pub mod rooms;
+pub mod router;
+pub mod selector;
 pub mod send;
+mod sender;
+pub mod session;
+pub mod throttle;
+pub mod vdom;
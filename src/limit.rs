@@ -0,0 +1,164 @@
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::throttle::Clock;
+
+/// What happens to a message when a connection goes over its `RateLimit`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exceeded {
+    /// The message is dropped before it is read
+    Drop,
+    /// The message is still handled but a warning is logged, use this to find the right limits
+    /// before they are enforced
+    Log,
+    /// The connection is closed with `CloseCode::Policy`
+    Close,
+}
+
+/// How much one connection can send every second, set with `Listener::rate_limit` <br />
+/// a connection can send a whole second of messages at once and then has to wait for the limit
+/// to fill up again
+/// ```no_run
+/// use razer::limit::{Exceeded, RateLimit};
+/// use razer::listener::Listener;
+/// # use razer::event::handler::EventHandler;
+/// # #[derive(Clone)]
+/// # pub struct Handler;
+/// # impl EventHandler for Handler {}
+///
+/// Listener::new()
+///     .rate_limit(RateLimit {
+///         messages: 100,
+///         bytes: 64 * 1024,
+///         exceeded: Exceeded::Close,
+///     })
+///     .max_connections_per_ip(16)
+///     .start(Handler)
+///     .unwrap();
+/// ```
+/// a message that is bigger than `bytes` is let through once the whole second is there, use
+/// `Listener::max_message_size` to stop those
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    /// Messages every second
+    pub messages: u32,
+    /// Bytes every second
+    pub bytes: u32,
+    /// What to do with the messages over the limit
+    pub exceeded: Exceeded,
+}
+
+struct Bucket {
+    rate: f64,
+    tokens: f64,
+}
+
+impl Bucket {
+    fn new(rate: u32) -> Bucket {
+        Bucket {
+            rate: rate as f64,
+            tokens: rate as f64,
+        }
+    }
+
+    fn fill(&mut self, seconds: f64) {
+        self.tokens = (self.tokens + seconds * self.rate).min(self.rate);
+    }
+
+    fn has(&self, amount: f64) -> bool {
+        self.tokens >= amount.min(self.rate)
+    }
+
+    fn take(&mut self, amount: f64) {
+        self.tokens -= amount.min(self.rate);
+    }
+}
+
+/// The `RateLimit` of one connection
+pub(crate) struct Limiter {
+    pub(crate) exceeded: Exceeded,
+    messages: Bucket,
+    bytes: Bucket,
+    clock: Arc<dyn Clock>,
+    last: Instant,
+}
+
+impl Limiter {
+    pub(crate) fn new(limit: RateLimit, clock: Arc<dyn Clock>) -> Limiter {
+        Limiter {
+            exceeded: limit.exceeded,
+            messages: Bucket::new(limit.messages),
+            bytes: Bucket::new(limit.bytes),
+            last: clock.now(),
+            clock,
+        }
+    }
+
+    /// If a message of `len` bytes is under the limit, it is only counted when it is
+    pub(crate) fn allow(&mut self, len: usize) -> bool {
+        let now = self.clock.now();
+        let seconds = now.saturating_duration_since(self.last).as_secs_f64();
+        self.last = now;
+        self.messages.fill(seconds);
+        self.bytes.fill(seconds);
+        let len = len as f64;
+        if self.messages.has(1.0) && self.bytes.has(len) {
+            self.messages.take(1.0);
+            self.bytes.take(len);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::throttle::FakeClock;
+
+    fn limiter(messages: u32, bytes: u32) -> (Limiter, Arc<FakeClock>) {
+        let clock = Arc::new(FakeClock::default());
+        let limit = RateLimit {
+            messages,
+            bytes,
+            exceeded: Exceeded::Drop,
+        };
+        (Limiter::new(limit, clock.clone()), clock)
+    }
+
+    #[test]
+    fn messages_fill_up_again_over_time() {
+        let (mut limiter, clock) = limiter(10, 1000);
+        for _ in 0..10 {
+            assert!(limiter.allow(1));
+        }
+        assert!(!limiter.allow(1));
+        clock.advance(100);
+        assert!(limiter.allow(1));
+        assert!(!limiter.allow(1));
+        // never more than a second of messages at once
+        clock.advance(10_000);
+        for _ in 0..10 {
+            assert!(limiter.allow(1));
+        }
+        assert!(!limiter.allow(1));
+    }
+
+    #[test]
+    fn bytes_are_limited_too() {
+        let (mut limiter, clock) = limiter(100, 1000);
+        assert!(limiter.allow(600));
+        assert!(!limiter.allow(600));
+        // a dropped message does not count
+        assert!(limiter.allow(400));
+        clock.advance(500);
+        assert!(limiter.allow(500));
+        assert!(!limiter.allow(1));
+        // bigger than a whole second, it has to wait until the limit is full
+        clock.advance(500);
+        assert!(!limiter.allow(5000));
+        clock.advance(500);
+        assert!(limiter.allow(5000));
+    }
+}
@@ -1,233 +1,1834 @@
 use std::collections::HashMap;
+use std::fmt;
 use std::fs::File;
-use std::io::Read;
+use std::io::{self, Read};
+use std::net::{IpAddr, SocketAddr};
+use std::panic;
+use std::path::Path;
 use std::rc::Rc;
-use std::sync::Mutex;
+use std::sync::atomic::Ordering;
+use std::sync::{mpsc, Arc};
 use std::thread;
+use std::time::{Duration, Instant};
 
-use lazy_static::lazy_static;
+use log::{debug, warn};
 use openssl::pkey::{PKey, Private};
 use openssl::ssl::{SslAcceptor, SslMethod, SslStream};
 use openssl::x509::X509;
-use razer_ws::{CloseCode, Handshake};
-use razer_ws::util::TcpStream;
+use razer_ws::util::{TcpStream, Token};
+use razer_ws::{CloseCode, Handshake, Request, Response};
 use serde_json::{json, Value};
 
-use crate::EventHandler;
+use crate::connections::ConnectionInfo;
+use crate::error::{Error, Result};
+use crate::event::handler::EventHandler;
+use crate::event::subscription::send_subscriptions;
+use crate::handshake::{normalize_origin, query, Csrf, Guard};
+use crate::http::{self, Asset, Http};
+use crate::identity::{Identity, Reject};
+use crate::limit::{Exceeded, Limiter, RateLimit};
+use crate::pool::{Mailbox, Overflow, Pool, Work};
+use crate::protocol::{ClientMessage, ServerMessage};
+use crate::registry::Registry;
+use crate::resume::{new_id, Away, Link, Parked, Resume, Sessions, SESSION_PARAM};
+use crate::send::send_message;
+use crate::session::SessionFactory;
+use crate::throttle::{Policies, Policy, SystemClock, Throttler};
 
-#[derive(Clone, Debug)]
+type Custom = HashMap<String, Arc<dyn Fn(Value, &crate::Sender) + Send + Sync>>;
+type Authenticate = Arc<dyn Fn(&Request) -> std::result::Result<Identity, Reject> + Send + Sync>;
+type Session<F> = Arc<Mailbox<<F as SessionFactory>::Session, Job>>;
+
+// the timeout that wakes a connection up when an event it held back can be handled
+const THROTTLE: Token = Token(1);
+
+#[derive(Clone)]
 pub struct Listener {
     cert: Option<X509>,
     key: Option<PKey<Private>>,
+    addresses: Vec<String>,
     port: u16,
+    settings: razer_ws::Settings,
+    custom: Custom,
+    workers: usize,
+    queue_depth: usize,
+    overflow: Overflow,
+    policies: Policies,
+    rate_limit: Option<RateLimit>,
+    max_connections_per_ip: Option<usize>,
+    guard: Guard,
+    authenticate: Option<Authenticate>,
+    resume: Option<Resume>,
+    http: Http,
 }
 
-struct Server<H: EventHandler + 'static + Copy> {
-    out: razer_ws::Sender,
-    handler: H,
+struct Server<F: SessionFactory> {
+    out: crate::Sender,
+    factory: Arc<F>,
+    custom: Arc<Custom>,
+    pool: Arc<Pool>,
+    queue_depth: usize,
+    overflow: Overflow,
+    http: Arc<Http>,
+    guard: Arc<Guard>,
+    authenticate: Option<Authenticate>,
+    identity: Option<Identity>,
+    session: Option<Session<F>>,
+    link: Option<Arc<Link>>,
+    resume: Option<Resume>,
+    sessions: Arc<Sessions<Session<F>>>,
+    throttler: Throttler,
+    // when the timeout for the throttler will fire
+    timer: Option<Instant>,
+    limiter: Option<Limiter>,
+    max_connections_per_ip: Option<usize>,
+    // the address this connection is counted against
+    address: Option<IpAddr>,
     ssl: Option<Rc<SslAcceptor>>,
 }
 
-lazy_static! {
-    pub static ref CONNECTIONS: Mutex<HashMap<usize, u32>> = Mutex::new(HashMap::new());
+/// A server that was started with `Listener::spawn`, it keeps running until `shutdown` or
+/// `shutdown_graceful` is called, even if the handle is dropped
+pub struct ServerHandle {
+    addresses: Vec<SocketAddr>,
+    broadcaster: crate::Sender,
+    thread: thread::JoinHandle<Result<()>>,
+}
+
+// everything a session gets from the event loop, in the order it happened
+enum Job {
+    Connect(ConnectionInfo),
+    Message(ClientMessage),
+    Disconnect(CloseCode, String),
+}
+
+impl Work for Job {
+    fn name(&self) -> Option<&str> {
+        match self {
+            Job::Message(ClientMessage::Event { name, .. }) => Some(name),
+            _ => None,
+        }
+    }
+
+    // `on_disconnect` is called even after the session panicked, so what it added in
+    // `on_connect` like a `Router` connection is removed
+    fn last(&self) -> bool {
+        matches!(self, Job::Disconnect(..))
+    }
 }
 
-impl<H: EventHandler + 'static + Copy> razer_ws::Handler for Server<H> {
+impl<F: SessionFactory> razer_ws::Handler for Server<F> {
+    // websockets are opened like normal and everything else is a page or a file
+    fn on_request(&mut self, req: &Request) -> razer_ws::Result<Response> {
+        let upgrade = req
+            .header("upgrade")
+            .is_some_and(|upgrade| upgrade.eq_ignore_ascii_case(b"websocket"));
+        if upgrade {
+            if self.out.registry().shutting_down.load(Ordering::Relaxed) {
+                return Ok(http::status(503, "Service Unavailable"));
+            }
+            if let Err(reason) = self.guard.check(req) {
+                warn!(
+                    "not opening a websocket for {:?}: {}",
+                    req.peer_addr(),
+                    reason
+                );
+                return Ok(http::status(403, "Forbidden"));
+            }
+            if let Some(authenticate) = &self.authenticate {
+                match authenticate(req) {
+                    Ok(identity) => self.identity = Some(identity),
+                    Err(reject) => {
+                        debug!(
+                            "not opening a websocket for {:?}: {}",
+                            req.peer_addr(),
+                            reject
+                        );
+                        return Ok(http::status(reject.status, &reject.reason));
+                    }
+                }
+            }
+            if !self.admit(req.peer_addr()) {
+                warn!("{:?} has too many connections open", req.peer_addr());
+                return Ok(http::status(429, "Too Many Requests"));
+            }
+            Response::from_request(req)
+        } else {
+            Ok(self.http.respond(req))
+        }
+    }
+
     fn upgrade_ssl_server(&mut self, sock: TcpStream) -> razer_ws::Result<SslStream<TcpStream>> {
         self.ssl.clone().unwrap().accept(sock).map_err(From::from)
     }
 
-    fn on_open(&mut self, _shake: Handshake) -> razer_ws::Result<()> {
-        CONNECTIONS
+    fn on_open(&mut self, shake: Handshake) -> razer_ws::Result<()> {
+        // the upgrade was accepted right before the server asked every website to leave
+        if self.out.registry().shutting_down.load(Ordering::Relaxed) {
+            return self
+                .out
+                .close_with_reason(CloseCode::Away, "the server is shutting down");
+        }
+        let resuming = self
+            .resume
+            .and_then(|_| query(shake.request.resource(), SESSION_PARAM))
+            .and_then(|id| self.sessions.take(&id, self.identity.as_ref()));
+        if let Some(parked) = resuming {
+            let from = parked.away.link.socket();
+            if parked.away.link.reopen((*self.out).clone()) {
+                self.sessions.resume(&parked, self.out.token().0, || {
+                    self.register(&parked.away.rooms)
+                });
+                crate::eval::moved(from, &self.out);
+                self.resumed(parked);
+                return Ok(());
+            }
+            // it sent too much while it was parked, so the website gets a new session
+            self.sessions.finish(parked);
+        }
+        self.sessions
+            .open(self.out.token().0, || self.register(&[]));
+        let id = self.resume.and_then(|_| {
+            new_id()
+                .map_err(|err| warn!("could not make a session id: {}", err))
+                .ok()
+        });
+        let link = Link::new((*self.out).clone(), self.out.registry().clone(), id.clone());
+        let custom = self.custom.clone();
+        let current = link.clone();
+        // the handlers always get the socket the session is on now
+        let session = Mailbox::new(
+            self.pool.clone(),
+            self.factory.create(),
+            self.queue_depth,
+            self.overflow,
+            move |handler: &mut F::Session, job| run_job(handler, &custom, job, &current.sender()),
+        );
+        if let Some(id) = id {
+            // sent first so the website has it before it can lose the socket
+            let message = ServerMessage::Session { id, resumed: false };
+            if let Err(err) = send_message(&link.sender(), &message) {
+                debug!(
+                    "could not send the session to {:?}: {}",
+                    self.out.token(),
+                    err
+                );
+            }
+        }
+        session.push(Job::Connect(ConnectionInfo {
+            identity: self.identity.clone(),
+            ..ConnectionInfo::from(&shake)
+        }));
+        self.session = Some(session);
+        self.link = Some(link);
+        Ok(())
+    }
+
+    fn on_close(&mut self, code: CloseCode, reason: &str) {
+        self.closed(code, reason, true);
+    }
+
+    // the event loop stops without waiting for the website to close the connection
+    fn on_shutdown(&mut self) {
+        self.closed(CloseCode::Away, "the server is shutting down", false);
+    }
+
+    fn on_message(&mut self, msg: razer_ws::Message) -> razer_ws::Result<()> {
+        // checked before the frame is read so a flood costs as little as possible
+        if let Some(limiter) = &mut self.limiter {
+            if !limiter.allow(msg.len()) {
+                match limiter.exceeded {
+                    Exceeded::Drop => {
+                        debug!(
+                            "{:?} is over its rate limit, dropping a frame",
+                            self.out.token()
+                        );
+                        return Ok(());
+                    }
+                    Exceeded::Log => warn!("{:?} is over its rate limit", self.out.token()),
+                    Exceeded::Close => {
+                        warn!("closing {:?}, it is over its rate limit", self.out.token());
+                        return self
+                            .out
+                            .close_with_reason(CloseCode::Policy, "rate limit exceeded");
+                    }
+                }
+            }
+        }
+        let message = match msg.as_text().map(ClientMessage::decode) {
+            Ok(Ok(message)) => message,
+            Ok(Err(err)) => {
+                warn!("dropping a frame from {:?}: {}", self.out.token(), err);
+                return Ok(());
+            }
+            Err(_) => {
+                warn!("dropping a binary frame from {:?}", self.out.token());
+                return Ok(());
+            }
+        };
+        match message {
+            // eval replies are handed straight to whoever is waiting, so a session can block on them
+            ClientMessage::EvalResult { id, result, error } => {
+                crate::eval::reply(&self.out, id, result, error)
+            }
+            ClientMessage::Event { name, event } => match self.throttler.pass(&name, event) {
+                Some(event) => self.dispatch(name, event),
+                None => self.wait(),
+            },
+            message => {
+                if let Some(session) = &self.session {
+                    session.push(Job::Message(message));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn on_timeout(&mut self, event: Token) -> razer_ws::Result<()> {
+        if event == THROTTLE {
+            self.timer = None;
+            for (name, event) in self.throttler.due() {
+                self.dispatch(name, event);
+            }
+            self.wait();
+        }
+        Ok(())
+    }
+}
+
+impl<F: SessionFactory> Server<F> {
+    // counts the connection against its address, false if the address already has as many as it
+    // can have
+    fn admit(&mut self, peer: Option<SocketAddr>) -> bool {
+        let (Some(max), Some(peer)) = (self.max_connections_per_ip, peer) else {
+            return true;
+        };
+        let mut addresses = self.out.registry().addresses.lock().unwrap();
+        let open = addresses.entry(peer.ip()).or_insert(0);
+        if *open >= max {
+            return false;
+        }
+        *open += 1;
+        self.address = Some(peer.ip());
+        true
+    }
+
+    // puts the connection in the registry and in `rooms`
+    fn register(&self, rooms: &[String]) {
+        let registry = self.out.registry();
+        registry
+            .connections
             .lock()
             .unwrap()
             .insert(self.out.token().0, self.out.connection_id());
-        Ok(())
+        if let Some(identity) = &self.identity {
+            registry.identities.lock().unwrap().insert(
+                self.out.token().0,
+                (self.out.connection_id(), identity.clone()),
+            );
+        }
+        for room in rooms {
+            crate::rooms::join(&self.out, room);
+        }
     }
 
-    fn on_close(&mut self, _code: CloseCode, _reason: &str) {
-        CONNECTIONS.lock().unwrap().remove(&self.out.token().0);
+    // takes the connection out of the registry and gives back the rooms it was in
+    fn unregister(&self) -> Vec<String> {
+        let registry = self.out.registry();
+        registry
+            .connections
+            .lock()
+            .unwrap()
+            .remove(&self.out.token().0);
+        registry
+            .identities
+            .lock()
+            .unwrap()
+            .remove(&self.out.token().0);
+        let rooms = crate::rooms::rooms_of(&self.out);
+        crate::rooms::leave_all(&self.out);
+        rooms
     }
 
-    fn on_message(&mut self, msg: razer_ws::Message) -> razer_ws::Result<()> {
-        let handler = self.handler;
-        let out = self.out.clone();
-        thread::spawn(move || {
-            let data: Value = serde_json::from_str(&msg.to_string()).unwrap();
-            match data["event_name"].as_str().unwrap() {
-                "abort" => handler.abort(data["event"].clone(), &out),
-                "afterprint" => handler.afterprint(data["event"].clone(), &out),
-                "animationend" => handler.animationend(data["event"].clone(), &out),
-                "animationiteration" => handler.animationiteration(data["event"].clone(), &out),
-                "animationstart" => handler.animationstart(data["event"].clone(), &out),
-                "beforeprint" => handler.beforeprint(data["event"].clone(), &out),
-                "beforeunload" => handler.beforeunload(data["event"].clone(), &out),
-                "blur" => handler.blur(data["event"].clone(), &out),
-                "canplay" => handler.canplay(data["event"].clone(), &out),
-                "canplaythrough" => handler.canplaythrough(data["event"].clone(), &out),
-                "change" => handler.change(data["event"].clone(), &out),
-                "click" => handler.click(data["event"].clone(), &out),
-                "contextmenu" => handler.contextmenu(data["event"].clone(), &out),
-                "copy" => handler.copy(data["event"].clone(), &out),
-                "cut" => handler.cut(data["event"].clone(), &out),
-                "dblclick" => handler.dblclick(data["event"].clone(), &out),
-                "drag" => handler.drag(data["event"].clone(), &out),
-                "dragend" => handler.dragend(data["event"].clone(), &out),
-                "dragenter" => handler.dragenter(data["event"].clone(), &out),
-                "dragleave" => handler.dragleave(data["event"].clone(), &out),
-                "dragover" => handler.dragover(data["event"].clone(), &out),
-                "dragstart" => handler.dragstart(data["event"].clone(), &out),
-                "drop" => handler.drop(data["event"].clone(), &out),
-                "durationchange" => handler.durationchange(data["event"].clone(), &out),
-                "ended" => handler.ended(data["event"].clone(), &out),
-                "error" => handler.error(data["event"].clone(), &out),
-                "focus" => handler.focus(data["event"].clone(), &out),
-                "focusin" => handler.focusin(data["event"].clone(), &out),
-                "focusout" => handler.focusout(data["event"].clone(), &out),
-                "fullscreenchange" => handler.fullscreenchange(data["event"].clone(), &out),
-                "fullscreenerror" => handler.fullscreenerror(data["event"].clone(), &out),
-                "hashchange" => handler.hashchange(data["event"].clone(), &out),
-                "input" => handler.input(data["event"].clone(), &out),
-                "invalid" => handler.invalid(data["event"].clone(), &out),
-                "keydown" => handler.keydown(data["event"].clone(), &out),
-                "keypress" => handler.keypress(data["event"].clone(), &out),
-                "keyup" => handler.keyup(data["event"].clone(), &out),
-                "load" => handler.load(data["event"].clone(), &out),
-                "loadeddata" => handler.loadeddata(data["event"].clone(), &out),
-                "loadedmetadata" => handler.loadedmetadata(data["event"].clone(), &out),
-                "loadstart" => handler.loadstart(data["event"].clone(), &out),
-                "message" => handler.message(data["event"].clone(), &out),
-                "mousedown" => handler.mousedown(data["event"].clone(), &out),
-                "mouseenter" => handler.mouseenter(data["event"].clone(), &out),
-                "mouseleave" => handler.mouseleave(data["event"].clone(), &out),
-                "mousemove" => handler.mousemove(data["event"].clone(), &out),
-                "mouseover" => handler.mouseover(data["event"].clone(), &out),
-                "mouseout" => handler.mouseout(data["event"].clone(), &out),
-                "mouseup" => handler.mouseup(data["event"].clone(), &out),
-                "mousewheel" => handler.mousewheel(data["event"].clone(), &out),
-                "offline" => handler.offline(data["event"].clone(), &out),
-                "online" => handler.online(data["event"].clone(), &out),
-                "open" => handler.open(data["event"].clone(), &out),
-                "pagehide" => handler.pagehide(data["event"].clone(), &out),
-                "pageshow" => handler.pageshow(data["event"].clone(), &out),
-                "paste" => handler.paste(data["event"].clone(), &out),
-                "pause" => handler.pause(data["event"].clone(), &out),
-                "play" => handler.play(data["event"].clone(), &out),
-                "playing" => handler.playing(data["event"].clone(), &out),
-                "popstate" => handler.popstate(data["event"].clone(), &out),
-                "progress" => handler.progress(data["event"].clone(), &out),
-                "ratechange" => handler.ratechange(data["event"].clone(), &out),
-                "resize" => handler.resize(data["event"].clone(), &out),
-                "reset" => handler.reset(data["event"].clone(), &out),
-                "scroll" => handler.scroll(data["event"].clone(), &out),
-                "search" => handler.search(data["event"].clone(), &out),
-                "seeked" => handler.seeked(data["event"].clone(), &out),
-                "seeking" => handler.seeking(data["event"].clone(), &out),
-                "select" => handler.select(data["event"].clone(), &out),
-                "show" => handler.show(data["event"].clone(), &out),
-                "stalled" => handler.stalled(data["event"].clone(), &out),
-                "storage" => handler.storage(data["event"].clone(), &out),
-                "submit" => handler.submit(data["event"].clone(), &out),
-                "suspend" => handler.suspend(data["event"].clone(), &out),
-                "timeupdate" => handler.timeupdate(data["event"].clone(), &out),
-                "toggle" => handler.toggle(data["event"].clone(), &out),
-                "touchcancel" => handler.touchcancel(data["event"].clone(), &out),
-                "touchend" => handler.touchend(data["event"].clone(), &out),
-                "touchmove" => handler.touchmove(data["event"].clone(), &out),
-                "touchstart" => handler.touchstart(data["event"].clone(), &out),
-                "transitionend" => handler.transitionend(data["event"].clone(), &out),
-                "unload" => handler.unload(data["event"].clone(), &out),
-                "volumechange" => handler.volumechange(data["event"].clone(), &out),
-                "waiting" => handler.waiting(data["event"].clone(), &out),
-                "wheel" => handler.wheel(data["event"].clone(), &out),
-                "eval" => handler.eval(
-                    json!({"event":data["event"].clone(), "data":data["data"].clone()}),
-                    &out,
-                ),
-                _ => {}
+    // the website came back with the id of a parked session, it already got everything that was
+    // sent while it was gone
+    fn resumed(&mut self, parked: Parked<Session<F>>) {
+        let link = parked.away.link;
+        if let Some(id) = link.id() {
+            let message = ServerMessage::Session {
+                id: id.to_string(),
+                resumed: true,
             };
-        });
-        Ok(())
+            if let Err(err) = send_message(&link.sender(), &message) {
+                debug!(
+                    "could not send the session to {:?}: {}",
+                    self.out.token(),
+                    err
+                );
+            }
+        }
+        self.session = Some(parked.mailbox);
+        self.link = Some(link);
+    }
+
+    // with `Listener::resume` the session is parked instead of ended if it can be resumed, its
+    // messages and evals wait for the website to come back
+    fn closed(&mut self, code: CloseCode, reason: &str, can_resume: bool) {
+        let (session, link) = (self.session.take(), self.link.take());
+        let parking = match (&link, self.resume) {
+            (Some(link), Some(resume))
+                if can_resume && !self.out.registry().shutting_down.load(Ordering::Relaxed) =>
+            {
+                link.id().map(|id| (id.to_string(), resume))
+            }
+            _ => None,
+        };
+        match (session, link, parking) {
+            (Some(session), Some(link), Some((id, resume))) => {
+                link.park(resume.buffer);
+                self.sessions.park(&id, session, resume.ttl, || Away {
+                    rooms: self.unregister(),
+                    socket: Some(link.socket()),
+                    link,
+                    identity: self.identity.clone(),
+                });
+            }
+            (session, link, _) => {
+                self.unregister();
+                crate::eval::close(&self.out);
+                if let Some(link) = link {
+                    link.close();
+                }
+                // the session is dropped once the worker is done with the last job
+                if let Some(session) = session {
+                    session.push(Job::Disconnect(code, reason.to_string()));
+                }
+            }
+        }
+    }
+
+    fn dispatch(&self, name: String, event: Value) {
+        if let Some(session) = &self.session {
+            let latest = self.throttler.policy(&name) == Some(Policy::Latest);
+            let job = Job::Message(ClientMessage::Event { name, event });
+            if latest {
+                session.push_latest(job);
+            } else {
+                session.push(job);
+            }
+        }
+    }
+
+    // asks the event loop to wake this connection up once an event that was held back is due
+    fn wait(&mut self) {
+        if let Some(next) = self.throttler.next() {
+            let at = Instant::now() + next;
+            if self.timer.is_none_or(|timer| at < timer) {
+                // rounded up so the event is due when the timeout fires
+                let ms = next.as_micros().div_ceil(1000) as u64;
+                match self.out.timeout(ms, THROTTLE) {
+                    Ok(()) => self.timer = Some(at),
+                    Err(err) => debug!(
+                        "could not wait for the events of {:?}: {}",
+                        self.out.token(),
+                        err
+                    ),
+                }
+            }
+        }
+    }
+}
+
+// ws-rs drops the handler once the socket is gone, even if the handshake never finished
+impl<F: SessionFactory> Drop for Server<F> {
+    fn drop(&mut self) {
+        if let Some(address) = self.address.take() {
+            let mut addresses = self.out.registry().addresses.lock().unwrap();
+            if let Some(open) = addresses.get_mut(&address) {
+                *open -= 1;
+                if *open == 0 {
+                    addresses.remove(&address);
+                }
+            }
+        }
+    }
+}
+
+// the jobs of one connection are run by one worker at a time, so its session can be used with
+// `&mut` and the events are handled in the order they came in
+fn run_job<H: EventHandler>(handler: &mut H, custom: &Custom, job: Job, out: &crate::Sender) {
+    match job {
+        Job::Connect(info) => {
+            handler.on_connect(info, out);
+            // the website waits for this before it sends any event
+            if let Err(err) = send_subscriptions(out, handler.subscriptions()) {
+                debug!("could not send the events to {:?}: {}", out.token(), err);
+            }
+        }
+        Job::Message(ClientMessage::Event { name, event }) => match custom.get(&name) {
+            Some(custom) => custom(event, out),
+            None => handler.on_event(&name, event, out),
+        },
+        Job::Message(ClientMessage::Eval { result, code }) => {
+            handler.eval(json!({"event": result, "data": code}), out)
+        }
+        Job::Message(ClientMessage::EvalResult { .. }) => {}
+        Job::Disconnect(code, reason) => handler.on_disconnect(code, &reason, out),
     }
 }
 
 impl Listener {
     #[must_use]
     pub fn new() -> Listener {
-        return Listener {
+        Listener {
             cert: None,
             key: None,
+            addresses: vec!["0.0.0.0".to_string()],
             port: 2794,
-        };
+            settings: razer_ws::Settings {
+                max_connections: 1024,
+                queue_size: 16,
+                ..razer_ws::Settings::default()
+            },
+            custom: HashMap::new(),
+            workers: thread::available_parallelism().map_or(1, |cpus| cpus.get()) * 4,
+            queue_depth: 256,
+            overflow: Overflow::Coalesce,
+            policies: HashMap::new(),
+            rate_limit: None,
+            max_connections_per_ip: None,
+            guard: Guard::default(),
+            authenticate: None,
+            resume: None,
+            http: Http::default(),
+        }
     }
 
-    pub fn with_key(&self, key: &str) -> Listener {
-        return Listener {
-            cert: self.clone().cert,
-            key: {
-                let data = read_file(key).unwrap();
-                Some(PKey::private_key_from_pem(data.as_ref()).unwrap())
-            },
-            port: self.clone().port,
+    /// This will read the private key for ssl from a pem file, `with_cert` has to be used too <br />
+    /// this will fail with `Error::Io` if the file can not be read and `Error::Tls` if it is not a
+    /// valid key
+    /// ```no_run
+    /// use razer::listener::Listener;
+    /// # use razer::event::handler::EventHandler;
+    /// # #[derive(Clone)]
+    /// # pub struct Handler;
+    /// # impl EventHandler for Handler {}
+    ///
+    /// fn main() -> razer::Result<()> {
+    ///     Listener::new()
+    ///         .with_cert("certificate.crt")?
+    ///         .with_key("privateKey.key")?
+    ///         .start(Handler)
+    /// }
+    /// ```
+    pub fn with_key(&self, key: &str) -> Result<Listener> {
+        let data = read_file(key)?;
+        Ok(Listener {
+            key: Some(PKey::private_key_from_pem(&data)?),
+            ..self.clone()
+        })
+    }
+
+    /// This will read the certificate for ssl from a pem file, `with_key` has to be used too <br />
+    /// this will fail with `Error::Io` if the file can not be read and `Error::Tls` if it is not a
+    /// valid certificate
+    pub fn with_cert(&self, cert: &str) -> Result<Listener> {
+        let data = read_file(cert)?;
+        Ok(Listener {
+            cert: Some(X509::from_pem(&data)?),
+            ..self.clone()
+        })
+    }
+
+    /// This will call `handler` for every event with this name, use it for events that your
+    /// website makes up itself
+    /// ```no_run
+    /// use razer::listener::Listener;
+    /// # use razer::event::handler::EventHandler;
+    /// # #[derive(Clone)]
+    /// # pub struct Handler;
+    /// # impl EventHandler for Handler {}
+    ///
+    /// Listener::new()
+    ///     .on("cart:add", |payload, _ctx| {
+    ///         println!("adding {} to the cart", payload["item"]);
+    ///     })
+    ///     .start(Handler)
+    ///     .unwrap();
+    /// ```
+    /// on the website you can send the event with
+    /// ```js
+    /// razer.emit("cart:add", {item: "shoes"});
+    /// ```
+    /// a name that is already a dom event like `click` will go to `handler` instead of the
+    /// `EventHandler`, and names that are not registered or a dom event go to
+    /// `EventHandler::on_unknown_event` <br />
+    /// the website always sends these events, even if `EventHandler::subscriptions` does not have
+    /// them
+    pub fn on<H>(&self, name: &str, handler: H) -> Listener
+    where
+        H: Fn(Value, &crate::Sender) + Send + Sync + 'static,
+    {
+        let mut custom = self.custom.clone();
+        custom.insert(name.to_string(), Arc::new(handler));
+        Listener {
+            custom,
+            ..self.clone()
+        }
+    }
+
+    /// How many threads run the events, the default is 4 for every cpu <br />
+    /// the events of one connection are always handled one at a time and in order, but different
+    /// connections run at the same time <br />
+    /// a handler that blocks, like one waiting on `EvalHandle::wait`, keeps its thread until it is
+    /// done
+    /// ```no_run
+    /// use razer::listener::Listener;
+    /// use razer::pool::Overflow;
+    /// # use razer::event::handler::EventHandler;
+    /// # #[derive(Clone)]
+    /// # pub struct Handler;
+    /// # impl EventHandler for Handler {}
+    ///
+    /// Listener::new()
+    ///     .workers(16)
+    ///     .queue_depth(32)
+    ///     .overflow(Overflow::Coalesce)
+    ///     .start(Handler)
+    ///     .unwrap();
+    /// ```
+    pub fn workers(&self, workers: usize) -> Listener {
+        Listener {
+            workers: workers.max(1),
+            ..self.clone()
+        }
+    }
+
+    /// How many events one connection can have waiting before `overflow` is used, the default is
+    /// 256
+    pub fn queue_depth(&self, queue_depth: usize) -> Listener {
+        Listener {
+            queue_depth,
+            ..self.clone()
+        }
+    }
+
+    /// What to do with events when the queue of a connection is full, the default is
+    /// `Overflow::Coalesce`
+    pub fn overflow(&self, overflow: Overflow) -> Listener {
+        Listener {
+            overflow,
+            ..self.clone()
+        }
+    }
+
+    /// How often events with this name reach the `EventHandler` of one connection, see `Policy`
+    /// <br />
+    /// this works for dom events and for the ones from `razer.emit`
+    pub fn policy(&self, name: &str, policy: Policy) -> Listener {
+        let mut policies = self.policies.clone();
+        policies.insert(name.to_string(), policy);
+        Listener {
+            policies,
+            ..self.clone()
+        }
+    }
+
+    /// How much one connection can send before `RateLimit::exceeded` is used, there is no limit by
+    /// default
+    pub fn rate_limit(&self, rate_limit: RateLimit) -> Listener {
+        Listener {
+            rate_limit: Some(rate_limit),
+            ..self.clone()
+        }
+    }
+
+    /// How many connections one ip address can have open at once, there is no limit by default
+    /// <br />
+    /// more connections get `429 Too Many Requests` instead of a websocket, behind a proxy every
+    /// connection comes from the address of the proxy
+    pub fn max_connections_per_ip(&self, max_connections_per_ip: usize) -> Listener {
+        Listener {
+            max_connections_per_ip: Some(max_connections_per_ip),
+            ..self.clone()
+        }
+    }
+
+    /// Only let websites from these origins open a websocket, like `https://example.com`, every
+    /// other request gets `403 Forbidden` <br />
+    /// without this any website can open a websocket to the server, and the browser sends the
+    /// cookies of your website with it
+    /// ```no_run
+    /// use razer::listener::Listener;
+    /// # use razer::event::handler::EventHandler;
+    /// # #[derive(Clone)]
+    /// # pub struct Handler;
+    /// # impl EventHandler for Handler {}
+    ///
+    /// Listener::new()
+    ///     .allowed_origins(&["https://example.com", "https://www.example.com"])
+    ///     .start(Handler)
+    ///     .unwrap();
+    /// ```
+    pub fn allowed_origins(&self, origins: &[&str]) -> Listener {
+        let mut guard = self.guard.clone();
+        guard.origins = Some(
+            origins
+                .iter()
+                .map(|origin| normalize_origin(origin))
+                .collect(),
+        );
+        Listener {
+            guard,
+            ..self.clone()
+        }
+    }
+
+    /// Only open a websocket if the request has a csrf token that `check` says is valid, every
+    /// other request gets `403 Forbidden`
+    /// ```no_run
+    /// use razer::handshake::Csrf;
+    /// use razer::listener::Listener;
+    /// # use razer::event::handler::EventHandler;
+    /// # #[derive(Clone)]
+    /// # pub struct Handler;
+    /// # impl EventHandler for Handler {}
+    /// # fn is_valid(token: &str) -> bool { !token.is_empty() }
+    ///
+    /// Listener::new()
+    ///     .csrf(Csrf::Query("csrf".to_string()), |token| is_valid(token))
+    ///     .start(Handler)
+    ///     .unwrap();
+    /// ```
+    /// with `Csrf::Query` the page puts the token in a meta tag before the script
+    /// ```html
+    /// <meta name="razer-csrf" content="the token">
+    /// <script src="/razer.js"></script>
+    /// ```
+    pub fn csrf<C>(&self, csrf: Csrf, check: C) -> Listener
+    where
+        C: Fn(&str) -> bool + Send + Sync + 'static,
+    {
+        let mut guard = self.guard.clone();
+        let mut http = self.http.clone();
+        http.csrf_param = match &csrf {
+            Csrf::Query(name) => Some(name.clone()),
+            Csrf::Cookie(_) => None,
         };
+        guard.csrf = Some((csrf, Arc::new(check)));
+        Listener {
+            guard,
+            http,
+            ..self.clone()
+        }
     }
 
-    pub fn with_cert(&self, cert: &str) -> Listener {
-        return Listener {
-            cert: {
-                let data = read_file(cert).unwrap();
-                Some(X509::from_pem(data.as_ref()).unwrap())
+    /// Run `authenticate` on every request that opens a websocket, before it is opened <br />
+    /// the `Identity` it returns can be read with `identity::identity` in every handler of the
+    /// connection and is in `ConnectionInfo::identity`, if it returns a `Reject` the request gets
+    /// that status instead of a websocket
+    /// ```no_run
+    /// use razer::identity::{Identity, Reject};
+    /// use razer::listener::Listener;
+    /// # use razer::event::handler::EventHandler;
+    /// # #[derive(Clone)]
+    /// # pub struct Handler;
+    /// # impl EventHandler for Handler {}
+    /// # fn user_of_token(token: &str) -> Option<String> { None }
+    ///
+    /// Listener::new()
+    ///     .authenticate(|req| {
+    ///         // a bearer token in the query string like `/?token=...`
+    ///         let token = req
+    ///             .resource()
+    ///             .split_once("?token=")
+    ///             .map(|(_, token)| token)
+    ///             .ok_or_else(Reject::unauthorized)?;
+    ///         let user = user_of_token(token).ok_or_else(Reject::forbidden)?;
+    ///         Ok(Identity::new(&user))
+    ///     })
+    ///     .start(Handler)
+    ///     .unwrap();
+    /// ```
+    pub fn authenticate<A>(&self, authenticate: A) -> Listener
+    where
+        A: Fn(&Request) -> std::result::Result<Identity, Reject> + Send + Sync + 'static,
+    {
+        Listener {
+            authenticate: Some(Arc::new(authenticate)),
+            ..self.clone()
+        }
+    }
+
+    /// Keep the session of a connection for `ttl` after its socket drops, like when a phone
+    /// changes networks, if the website opens a new socket in that time it gets the same session
+    /// back <br />
+    /// up to `buffer` bytes of messages that the session sends while the website is gone are kept
+    /// and sent once it is back, a session that sends more than that can not be resumed and the
+    /// website reloads instead <br />
+    /// `EventHandler::on_disconnect` is called when the session ends and not when the socket
+    /// drops, and a resumed session joins its rooms again
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// use razer::listener::Listener;
+    /// # use razer::event::handler::EventHandler;
+    /// # #[derive(Clone)]
+    /// # pub struct Handler;
+    /// # impl EventHandler for Handler {}
+    ///
+    /// Listener::new()
+    ///     .resume(Duration::from_secs(30), 256 * 1024)
+    ///     .start(Handler)
+    ///     .unwrap();
+    /// ```
+    pub fn resume(&self, ttl: Duration, buffer: usize) -> Listener {
+        Listener {
+            resume: Some(Resume { ttl, buffer }),
+            ..self.clone()
+        }
+    }
+
+    /// Listen on this address instead of `0.0.0.0`, it can be an ipv4 or ipv6 address or a host
+    /// name, and it can have its own port like `127.0.0.1:8080` or `[::1]:8080` <br />
+    /// addresses without a port use the one from `port`
+    /// ```no_run
+    /// use razer::listener::Listener;
+    /// # use razer::event::handler::EventHandler;
+    /// # #[derive(Clone)]
+    /// # pub struct Handler;
+    /// # impl EventHandler for Handler {}
+    ///
+    /// // the same port on every ipv4 and ipv6 address
+    /// Listener::new()
+    ///     .address("0.0.0.0")
+    ///     .bind("::")
+    ///     .port(8080)
+    ///     .start(Handler)
+    ///     .unwrap();
+    /// ```
+    pub fn address(&self, address: &str) -> Listener {
+        Listener {
+            addresses: vec![address.to_string()],
+            ..self.clone()
+        }
+    }
+
+    /// Listen on this address too, every address is served by the same server so a connection
+    /// from one can be sent to from the others <br />
+    /// an ipv6 address like `::` only takes ipv6 connections, so bind `0.0.0.0` as well for ipv4
+    pub fn bind(&self, address: &str) -> Listener {
+        let mut addresses = self.addresses.clone();
+        addresses.push(address.to_string());
+        Listener {
+            addresses,
+            ..self.clone()
+        }
+    }
+
+    /// The port for the addresses that do not have one, the default is 2794
+    pub fn port(&self, port: u16) -> Listener {
+        Listener {
+            port,
+            ..self.clone()
+        }
+    }
+
+    /// How many connections can be open at once, the default is 1024 <br />
+    /// new connections are turned away once this is reached, room for every connection is made
+    /// when the server starts so a high number uses more memory even when nobody is connected
+    pub fn max_connections(&self, max_connections: usize) -> Listener {
+        self.with_settings(|settings| settings.max_connections = max_connections)
+    }
+
+    /// How many messages every connection can have waiting to be sent, the default is 16 <br />
+    /// the queue is shared by every connection, so one connection can use more than this if the
+    /// others are not using theirs, and sending fails with `Error::WebSocket` once it is full
+    pub fn queue_size(&self, queue_size: usize) -> Listener {
+        self.with_settings(|settings| settings.queue_size = queue_size)
+    }
+
+    /// The largest message in bytes the website can send, a connection that sends more than this
+    /// is closed, the default is no limit
+    pub fn max_message_size(&self, max_message_size: usize) -> Listener {
+        self.with_settings(|settings| settings.max_message_size = max_message_size)
+    }
+
+    /// Send messages right away instead of waiting to put small ones together, the default is
+    /// false
+    pub fn tcp_nodelay(&self, tcp_nodelay: bool) -> Listener {
+        self.with_settings(|settings| settings.tcp_nodelay = tcp_nodelay)
+    }
+
+    /// Use these settings for the websocket server, this is for the settings that do not have
+    /// their own method <br />
+    /// it replaces what `max_connections`, `queue_size`, `max_message_size` and `tcp_nodelay`
+    /// set, so call those after this one, and `encrypt_server` is always set from `with_key` and
+    /// `with_cert`
+    /// ```no_run
+    /// use razer::listener::Listener;
+    /// use razer::Settings;
+    /// # use razer::event::handler::EventHandler;
+    /// # #[derive(Clone)]
+    /// # pub struct Handler;
+    /// # impl EventHandler for Handler {}
+    ///
+    /// Listener::new()
+    ///     .settings(Settings {
+    ///         out_buffer_capacity: 64 * 1024,
+    ///         ..Settings::default()
+    ///     })
+    ///     .max_connections(50_000)
+    ///     .start(Handler)
+    ///     .unwrap();
+    /// ```
+    pub fn settings(&self, settings: razer_ws::Settings) -> Listener {
+        Listener {
+            settings,
+            ..self.clone()
+        }
+    }
+
+    fn with_settings(&self, change: impl FnOnce(&mut razer_ws::Settings)) -> Listener {
+        let mut settings = self.settings;
+        change(&mut settings);
+        self.settings(settings)
+    }
+
+    /// Serve this html when the website is opened on `/`, the page can load the script from
+    /// `/razer.js` so nothing has to be hosted anywhere else
+    /// ```no_run
+    /// use razer::listener::Listener;
+    /// # use razer::event::handler::EventHandler;
+    /// # #[derive(Clone)]
+    /// # pub struct Handler;
+    /// # impl EventHandler for Handler {}
+    ///
+    /// Listener::new()
+    ///     .index("<!DOCTYPE html><script src=\"/razer.js\"></script><h1>Hello</h1>")
+    ///     .file("/style.css", "static/style.css")
+    ///     .directory("/images", "static/images")
+    ///     .start(Handler)
+    ///     .unwrap();
+    /// ```
+    /// then open `http://localhost:2794` in the browser
+    pub fn index(&self, html: &str) -> Listener {
+        self.route(
+            "/",
+            Asset::Bytes {
+                content_type: "text/html; charset=utf-8".to_string(),
+                body: Arc::new(html.as_bytes().to_vec()),
             },
-            key: self.clone().key,
-            port: self.clone().port,
+        )
+    }
+
+    /// Serve a file on `route`, the file is read again for every request so it can be changed
+    /// while the server is running <br />
+    /// the content type comes from the extension of the file
+    pub fn file(&self, route: &str, path: impl AsRef<Path>) -> Listener {
+        self.route(route, Asset::File(path.as_ref().to_path_buf()))
+    }
+
+    /// Serve every file in a directory under `route`, so `directory("/static", "public")` serves
+    /// `public/app.css` on `/static/app.css` <br />
+    /// a directory serves its `index.html` and paths with `..` are not served
+    pub fn directory(&self, route: &str, dir: impl AsRef<Path>) -> Listener {
+        self.route(route, Asset::Directory(dir.as_ref().to_path_buf()))
+    }
+
+    /// The host and port the website has to use to reach the server, this is put into
+    /// `/razer.js` <br />
+    /// by default the script connects to the same host and port it was loaded from, set this
+    /// when the pages are on another server or the websocket goes through a proxy
+    pub fn public_address(&self, address: &str) -> Listener {
+        let mut http = self.http.clone();
+        http.public_address = Some(address.to_string());
+        Listener {
+            http,
+            ..self.clone()
+        }
+    }
+
+    /// The path `/razer.js` opens the websocket on, the default is `/` <br />
+    /// it shows up as `ConnectionInfo::resource`
+    pub fn socket_path(&self, path: &str) -> Listener {
+        let mut http = self.http.clone();
+        http.socket_path = path.to_string();
+        Listener {
+            http,
+            ..self.clone()
+        }
+    }
+
+    fn route(&self, route: &str, asset: Asset) -> Listener {
+        let mut http = self.http.clone();
+        http.routes.insert(route.to_string(), asset);
+        Listener {
+            http,
+            ..self.clone()
+        }
+    }
+
+    /// This will start the server and block until it stops <br />
+    /// `factory` can be an `EventHandler` that is `Clone`, a `Router` or any other `SessionFactory`
+    /// <br />
+    /// this will fail with `Error::Tls` if the key and certificate do not work together,
+    /// `Error::Bind` if one of the addresses can not be listened on and `Error::Io` if the threads
+    /// for the events can not be started
+    pub fn start<F: SessionFactory>(&self, factory: F) -> Result<()> {
+        self.build(factory, self.registry())?.run()?;
+        Ok(())
+    }
+
+    /// This will start the server on its own thread and return once it is listening, so it can be
+    /// used inside of a bigger program or a test <br />
+    /// it fails the same way as `start`
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use razer::listener::Listener;
+    /// # use razer::event::handler::EventHandler;
+    /// # #[derive(Clone)]
+    /// # pub struct Handler;
+    /// # impl EventHandler for Handler {}
+    ///
+    /// fn main() -> razer::Result<()> {
+    ///     // port 0 lets the system pick a port that is free
+    ///     let server = Listener::new().address("127.0.0.1").port(0).spawn(Handler)?;
+    ///     println!("listening on {}", server.local_addr());
+    ///     server.shutdown_graceful(Duration::from_secs(5))
+    /// }
+    /// ```
+    pub fn spawn<F: SessionFactory>(&self, factory: F) -> Result<ServerHandle> {
+        let listener = self.clone();
+        let registry = self.registry();
+        let shared = registry.clone();
+        let (ready, started) = mpsc::channel();
+        // the server is made on its own thread because the tls acceptor can not be sent to it
+        let thread = thread::Builder::new()
+            .name("razer-server".to_string())
+            .spawn(move || {
+                let server = match listener.build(factory, shared) {
+                    Ok(server) => server,
+                    Err(err) => {
+                        let _ = ready.send(Err(err));
+                        return Ok(());
+                    }
+                };
+                let addresses = server.local_addrs().map_err(Error::from);
+                let _ = ready.send(addresses.map(|addresses| (addresses, server.broadcaster())));
+                server.run()?;
+                Ok(())
+            })?;
+        let (addresses, broadcaster) = match started.recv() {
+            Ok(started) => started?,
+            // the thread only hangs up without sending anything if it panicked
+            Err(_) => panic::resume_unwind(thread.join().unwrap_err()),
         };
+        Ok(ServerHandle {
+            addresses,
+            broadcaster: crate::Sender::new(broadcaster, registry),
+            thread,
+        })
     }
 
-    pub fn start<H: EventHandler + 'static + Copy>(&self, handler: H) {
-        if self.key.is_some() && self.cert.is_some() {
-            let acceptor = Rc::new({
-                let mut builder = SslAcceptor::mozilla_intermediate(SslMethod::tls()).unwrap();
-                builder.set_private_key(&self.clone().key.unwrap()).unwrap();
-                builder
-                    .set_certificate(&self.clone().cert.unwrap())
-                    .unwrap();
-
-                builder.build()
-            });
-
-            razer_ws::Builder::new()
-                .with_settings(razer_ws::Settings {
-                    encrypt_server: true,
-                    ..razer_ws::Settings::default()
-                })
-                .build(|out: razer_ws::Sender| Server {
-                    out,
-                    handler,
-                    ssl: Some(acceptor.clone()),
-                })
-                .unwrap()
-                .listen(format!("0.0.0.0:{}", self.port))
-                .unwrap();
-        } else {
-            razer_ws::Builder::new()
-                .with_settings(razer_ws::Settings {
-                    ..razer_ws::Settings::default()
-                })
-                .build(|out: razer_ws::Sender| Server {
-                    out,
-                    handler,
-                    ssl: None,
-                })
-                .unwrap()
-                .listen(format!("0.0.0.0:{}", self.port))
-                .unwrap();
+    // what the new server knows about its connections, the names from `on` are in it so the
+    // website always sends them
+    fn registry(&self) -> Arc<Registry> {
+        Arc::new(Registry {
+            custom: self.custom.keys().cloned().collect(),
+            ..Registry::default()
+        })
+    }
+
+    fn build<F: SessionFactory>(
+        &self,
+        factory: F,
+        registry: Arc<Registry>,
+    ) -> Result<razer_ws::WebSocket<impl razer_ws::Factory<Handler = Server<F>>>> {
+        let factory = Arc::new(factory);
+        let custom = Arc::new(self.custom.clone());
+        let pool = Pool::new(self.workers)?;
+        let ssl = match (&self.key, &self.cert) {
+            (Some(key), Some(cert)) => {
+                let mut builder = SslAcceptor::mozilla_intermediate(SslMethod::tls())?;
+                builder.set_private_key(key)?;
+                builder.set_certificate(cert)?;
+                builder.check_private_key()?;
+                Some(Rc::new(builder.build()))
+            }
+            _ => None,
         };
+        let queue_depth = self.queue_depth;
+        let overflow = self.overflow;
+        let http = Arc::new(self.http.clone());
+        let guard = Arc::new(self.guard.clone());
+        let authenticate = self.authenticate.clone();
+        let policies = Arc::new(self.policies.clone());
+        let clock = Arc::new(SystemClock);
+        let rate_limit = self.rate_limit;
+        let max_connections_per_ip = self.max_connections_per_ip;
+        let resume = self.resume;
+        let sessions = Arc::new(Sessions::new(
+            registry.clone(),
+            clock.clone(),
+            |session: Session<F>| {
+                session.push(Job::Disconnect(
+                    CloseCode::Away,
+                    "the session expired".to_string(),
+                ))
+            },
+        ));
+        if let Some(resume) = resume {
+            sessions.sweep_every(
+                (resume.ttl / 4).clamp(Duration::from_millis(10), Duration::from_secs(1)),
+            )?;
+        }
+        let mut server = razer_ws::Builder::new()
+            .with_settings(razer_ws::Settings {
+                encrypt_server: ssl.is_some(),
+                ..self.settings
+            })
+            .build(move |out: razer_ws::Sender| Server {
+                out: crate::Sender::new(out, registry.clone()),
+                factory: factory.clone(),
+                custom: custom.clone(),
+                pool: pool.clone(),
+                queue_depth,
+                overflow,
+                http: http.clone(),
+                guard: guard.clone(),
+                authenticate: authenticate.clone(),
+                identity: None,
+                session: None,
+                link: None,
+                resume,
+                sessions: sessions.clone(),
+                throttler: Throttler::new(policies.clone(), clock.clone()),
+                timer: None,
+                limiter: rate_limit.map(|limit| Limiter::new(limit, clock.clone())),
+                max_connections_per_ip,
+                address: None,
+                ssl: ssl.clone(),
+            })?;
+        for address in &self.addresses {
+            let address = with_port(address, self.port);
+            server = server.bind(&address).map_err(|source| Error::Bind {
+                address,
+                source: Box::new(source),
+            })?;
+        }
+        Ok(server)
+    }
+}
+
+impl ServerHandle {
+    /// The first address the server is listening on, this has the port that was picked if the
+    /// port was 0
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addresses[0]
+    }
+
+    /// Every address the server is listening on, in the order they were added to the `Listener`
+    pub fn local_addrs(&self) -> &[SocketAddr] {
+        &self.addresses
+    }
+
+    /// A sender for every connection of this server, it can be given to `send::broadcast`,
+    /// `dom::broadcast` and the other functions that send to everyone
+    /// ```
+    /// use razer::event::event_type::Event::JS;
+    /// use razer::listener::ServerHandle;
+    /// use razer::send::broadcast;
+    ///
+    /// fn announce(server: &ServerHandle) -> razer::Result<()> {
+    ///     broadcast(&server.broadcaster(), JS, "alert(\"the server restarts soon\")")
+    /// }
+    /// ```
+    pub fn broadcaster(&self) -> crate::Sender {
+        self.broadcaster.clone()
+    }
+
+    /// How many connections are open
+    pub fn connections(&self) -> usize {
+        crate::connections::count(&self.broadcaster)
+    }
+
+    /// Stop the server right away and wait for it to stop, the connections are dropped without
+    /// waiting for the websites to close them <br />
+    /// every session still gets `on_disconnect`
+    pub fn shutdown(self) -> Result<()> {
+        let sent = self.broadcaster.shutdown();
+        join(self.thread)?;
+        sent.map_err(Error::from)
+    }
+
+    /// Ask every website to close its connection, wait until they did or `timeout` is over and
+    /// then stop the server like `shutdown` <br />
+    /// no new connection is accepted once this is called and with `Listener::resume` the sessions
+    /// are ended instead of parked, `razer.js` does not reconnect after this close
+    pub fn shutdown_graceful(self, timeout: Duration) -> Result<()> {
+        let deadline = Instant::now() + timeout;
+        self.broadcaster
+            .registry()
+            .shutting_down
+            .store(true, Ordering::Relaxed);
+        if self
+            .broadcaster
+            .close_with_reason(CloseCode::Away, "the server is shutting down")
+            .is_ok()
+        {
+            while self.connections() > 0 {
+                let now = Instant::now();
+                if now >= deadline {
+                    break;
+                }
+                thread::sleep((deadline - now).min(Duration::from_millis(10)));
+            }
+        }
+        self.shutdown()
     }
 }
 
-fn read_file(name: &str) -> std::io::Result<Vec<u8>> {
+impl fmt::Debug for ServerHandle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ServerHandle")
+            .field("addresses", &self.addresses)
+            .field("connections", &self.connections())
+            .finish()
+    }
+}
+
+// a panic on the server thread is passed on to whoever waits for it
+fn join(thread: thread::JoinHandle<Result<()>>) -> Result<()> {
+    match thread.join() {
+        Ok(result) => result,
+        Err(panic) => panic::resume_unwind(panic),
+    }
+}
+
+impl fmt::Debug for Listener {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Listener")
+            .field("cert", &self.cert)
+            .field("key", &self.key)
+            .field("addresses", &self.addresses)
+            .field("port", &self.port)
+            .field("settings", &self.settings)
+            .field("custom", &self.custom.keys().collect::<Vec<_>>())
+            .field("workers", &self.workers)
+            .field("queue_depth", &self.queue_depth)
+            .field("overflow", &self.overflow)
+            .field("policies", &self.policies)
+            .field("rate_limit", &self.rate_limit)
+            .field("max_connections_per_ip", &self.max_connections_per_ip)
+            .field("guard", &self.guard)
+            .field("authenticate", &self.authenticate.is_some())
+            .field("resume", &self.resume)
+            .field("routes", &self.http.routes.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl Default for Listener {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// the address with the port added if it does not have one
+fn with_port(address: &str, port: u16) -> String {
+    if address.parse::<SocketAddr>().is_ok() {
+        return address.to_string();
+    }
+    let ip = address.trim_start_matches('[').trim_end_matches(']');
+    match ip.parse::<IpAddr>() {
+        Ok(ip) => SocketAddr::new(ip, port).to_string(),
+        // a host name that already has a port
+        Err(_) if address.contains(':') => address.to_string(),
+        Err(_) => format!("{}:{}", address, port),
+    }
+}
+
+fn read_file(name: &str) -> io::Result<Vec<u8>> {
     let mut file = File::open(name)?;
     let mut buf = Vec::new();
     file.read_to_end(&mut buf)?;
     Ok(buf)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::{ErrorKind, Write};
+    use std::net::TcpStream;
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::event::event_type::Event::JS;
+    use crate::event::types::MouseEvent;
+    use crate::protocol::ServerMessage;
+    use crate::router::Router;
+
+    #[derive(Clone)]
+    struct Handler(Arc<Mutex<Vec<CloseCode>>>);
+
+    impl EventHandler for Handler {
+        fn on_disconnect(&mut self, code: CloseCode, _reason: &str, _ctx: &crate::Sender) {
+            self.0.lock().unwrap().push(code);
+        }
+    }
+
+    const HANDSHAKE: &[u8] = b"GET / HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\n\
+        Connection: Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+        Sec-WebSocket-Version: 13\r\n\r\n";
+
+    // sends the request that opens a websocket on `resource`
+    fn request(address: SocketAddr, resource: &str) -> TcpStream {
+        let mut stream = TcpStream::connect(address).unwrap();
+        let handshake = String::from_utf8_lossy(HANDSHAKE).replacen('/', resource, 1);
+        stream.write_all(handshake.as_bytes()).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        stream
+    }
+
+    fn connect(address: SocketAddr) -> TcpStream {
+        connect_to(address, "/")
+    }
+
+    fn connect_to(address: SocketAddr, resource: &str) -> TcpStream {
+        let mut stream = request(address, resource);
+        let mut response = Vec::new();
+        let mut byte = [0; 1];
+        while !response.ends_with(b"\r\n\r\n") {
+            stream.read_exact(&mut byte).unwrap();
+            response.push(byte[0]);
+        }
+        assert!(response.starts_with(b"HTTP/1.1 101"));
+        stream
+    }
+
+    // the next text frame from the server, they are never masked
+    fn read_message(stream: &mut TcpStream) -> ServerMessage {
+        let mut head = [0; 2];
+        stream.read_exact(&mut head).unwrap();
+        assert_eq!(head[0], 0x81);
+        let len = match head[1] {
+            126 => {
+                let mut len = [0; 2];
+                stream.read_exact(&mut len).unwrap();
+                u16::from_be_bytes(len) as usize
+            }
+            len => len as usize,
+        };
+        let mut payload = vec![0; len];
+        stream.read_exact(&mut payload).unwrap();
+        ServerMessage::decode(std::str::from_utf8(&payload).unwrap()).unwrap()
+    }
+
+    // a text frame from the website, these always have a mask
+    fn send_message(stream: &mut TcpStream, message: &ClientMessage) {
+        let payload = message.encode().into_bytes();
+        assert!(payload.len() < 126);
+        let mask = [1, 2, 3, 4];
+        let mut frame = vec![0x81, 0x80 | payload.len() as u8];
+        frame.extend_from_slice(&mask);
+        frame.extend(
+            payload
+                .iter()
+                .zip(mask.iter().cycle())
+                .map(|(byte, mask)| byte ^ mask),
+        );
+        stream.write_all(&frame).unwrap();
+    }
+
+    fn wait_until(done: impl Fn() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !done() {
+            assert!(Instant::now() < deadline, "timed out");
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[derive(Default)]
+    struct Events(Vec<String>);
+
+    impl EventHandler for Events {
+        fn click(&mut self, _event: MouseEvent, _ctx: &crate::Sender) {
+            self.0.push("click".to_string());
+        }
+
+        fn on_unknown_event(&mut self, name: &str, _event: Value, _ctx: &crate::Sender) {
+            self.0.push(format!("unknown {}", name));
+        }
+    }
+
+    #[test]
+    fn registered_names_go_to_their_closure() {
+        let (_socket, ctx) = crate::sender::detached(Arc::default(), 0, 0);
+        let added = Arc::new(Mutex::new(Vec::new()));
+        let log = added.clone();
+        let mut custom = Custom::new();
+        custom.insert(
+            "cart:add".to_string(),
+            Arc::new(move |payload, _ctx| log.lock().unwrap().push(payload)),
+        );
+        let mut handler = Events::default();
+        for (name, event) in [
+            ("cart:add", json!({"item": "shoes"})),
+            ("click", json!({})),
+            ("editor:save", json!({})),
+        ] {
+            let event = ClientMessage::Event {
+                name: name.to_string(),
+                event,
+            };
+            run_job(&mut handler, &custom, Job::Message(event), &ctx);
+        }
+        assert_eq!(*added.lock().unwrap(), vec![json!({"item": "shoes"})]);
+        assert_eq!(handler.0, vec!["click", "unknown editor:save"]);
+    }
+
+    #[test]
+    fn a_spawned_server_can_be_stopped() {
+        let server = Listener::new()
+            .address("127.0.0.1")
+            .port(0)
+            .spawn(Handler(Arc::default()))
+            .unwrap();
+        let address = server.local_addr();
+        assert_ne!(address.port(), 0);
+        server.shutdown().unwrap();
+        // the port is free again
+        Listener::new()
+            .address(&address.to_string())
+            .spawn(Handler(Arc::default()))
+            .unwrap()
+            .shutdown()
+            .unwrap();
+    }
+
+    #[test]
+    fn graceful_shutdown_closes_every_connection() {
+        let closed = Arc::new(Mutex::new(Vec::new()));
+        let server = Listener::new()
+            .address("127.0.0.1")
+            .port(0)
+            .spawn(Handler(closed.clone()))
+            .unwrap();
+        let mut stream = connect(server.local_addr());
+        wait_until(|| server.connections() == 1);
+        // the client never answers the close frame, so this waits for the whole timeout
+        server
+            .shutdown_graceful(Duration::from_millis(100))
+            .unwrap();
+        let mut rest = Vec::new();
+        let _ = stream.read_to_end(&mut rest);
+        let close = rest.iter().position(|byte| *byte == 0x88);
+        assert!(close.is_some(), "no close frame in {:?}", rest);
+        wait_until(|| *closed.lock().unwrap() == vec![CloseCode::Away]);
+    }
+
+    #[test]
+    fn graceful_shutdown_ends_resumable_sessions() {
+        let closed = Arc::new(Mutex::new(Vec::new()));
+        let server = Listener::new()
+            .address("127.0.0.1")
+            .port(0)
+            .resume(Duration::from_secs(10), 1024)
+            .spawn(Handler(closed.clone()))
+            .unwrap();
+        let (address, registry) = (server.local_addr(), server.broadcaster.registry().clone());
+        let mut stream = connect(address);
+        assert!(matches!(
+            read_message(&mut stream),
+            ServerMessage::Session { .. }
+        ));
+        read_message(&mut stream);
+        let started = Instant::now();
+        let shutdown = thread::spawn(move || server.shutdown_graceful(Duration::from_secs(10)));
+        wait_until(|| registry.shutting_down.load(Ordering::Relaxed));
+        let mut late = request(address, "/");
+        let mut response = String::new();
+        late.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 503"), "{}", response);
+        // 1001 tells razer.js not to come back
+        let mut head = [0; 2];
+        stream.read_exact(&mut head).unwrap();
+        assert_eq!(head[0], 0x88);
+        let mut payload = vec![0; head[1] as usize];
+        stream.read_exact(&mut payload).unwrap();
+        assert_eq!(payload[..2], [0x03, 0xe9]);
+        let mask = [1, 2, 3, 4];
+        stream
+            .write_all(&[0x88, 0x82, 1, 2, 3, 4, 0x03 ^ mask[0], 0xe9 ^ mask[1]])
+            .unwrap();
+        shutdown.join().unwrap().unwrap();
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(registry.parked.lock().unwrap().is_empty());
+        wait_until(|| *closed.lock().unwrap() == vec![CloseCode::Away]);
+    }
+
+    #[test]
+    fn builder_errors_keep_their_source() {
+        use std::error::Error as _;
+
+        let missing = Listener::new().with_key("does/not/exist.key").unwrap_err();
+        assert!(matches!(missing, Error::Io(_)));
+        assert!(missing.source().is_some());
+        let path = std::env::temp_dir().join(format!("razer-not-pem-{}", std::process::id()));
+        std::fs::write(&path, "not a pem file").unwrap();
+        let path = path.to_str().unwrap();
+        assert!(matches!(
+            Listener::new().with_cert(path).unwrap_err(),
+            Error::Tls(_)
+        ));
+        assert!(matches!(
+            Listener::new().with_key(path).unwrap_err(),
+            Error::Tls(_)
+        ));
+        std::fs::remove_file(path).unwrap();
+        // the port is already taken
+        let server = Listener::new()
+            .address("127.0.0.1")
+            .port(0)
+            .spawn(Handler(Arc::default()))
+            .unwrap();
+        let taken = server.local_addr().to_string();
+        match Listener::new()
+            .address(&taken)
+            .spawn(Handler(Arc::default()))
+        {
+            Err(err @ Error::Bind { .. }) => {
+                assert!(err.to_string().contains(&taken));
+                assert!(err.source().is_some());
+            }
+            other => panic!("{:?}", other.map(|server| server.local_addr())),
+        }
+        server.shutdown().unwrap();
+    }
+
+    #[test]
+    fn addresses_get_the_port() {
+        assert_eq!(with_port("0.0.0.0", 2794), "0.0.0.0:2794");
+        assert_eq!(with_port("127.0.0.1:8080", 2794), "127.0.0.1:8080");
+        assert_eq!(with_port("::", 2794), "[::]:2794");
+        assert_eq!(with_port("[::1]", 2794), "[::1]:2794");
+        assert_eq!(with_port("[::1]:8080", 2794), "[::1]:8080");
+        assert_eq!(with_port("localhost", 2794), "localhost:2794");
+        assert_eq!(with_port("localhost:8080", 2794), "localhost:8080");
+    }
+
+    #[test]
+    fn servers_only_see_their_own_connections() {
+        let spawn = || {
+            Listener::new()
+                .address("127.0.0.1")
+                .port(0)
+                .spawn(Handler(Arc::default()))
+                .unwrap()
+        };
+        let (first, second) = (spawn(), spawn());
+        let mut one = connect(first.local_addr());
+        let mut two = connect(second.local_addr());
+        // the handler does not say which events it uses so it gets all of them
+        for stream in [&mut one, &mut two] {
+            assert_eq!(
+                read_message(stream),
+                ServerMessage::Subscribe { events: None }
+            );
+        }
+        wait_until(|| first.connections() == 1 && second.connections() == 1);
+        // both connections have the same token on their own server
+        assert_eq!(
+            crate::connections::tokens(&first.broadcaster()),
+            crate::connections::tokens(&second.broadcaster())
+        );
+        crate::send::broadcast(&first.broadcaster(), JS, "1 + 1").unwrap();
+        assert_eq!(
+            read_message(&mut one),
+            ServerMessage::Js {
+                code: "1 + 1".to_string()
+            }
+        );
+        let mut frame = [0; 1];
+        two.set_read_timeout(Some(Duration::from_millis(100)))
+            .unwrap();
+        let err = two.read_exact(&mut frame).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            ErrorKind::WouldBlock | ErrorKind::TimedOut
+        ));
+        first.shutdown().unwrap();
+        second.shutdown().unwrap();
+    }
+
+    #[derive(Clone)]
+    struct Clicks;
+
+    impl EventHandler for Clicks {
+        fn subscriptions(&self) -> Option<Vec<String>> {
+            Some(vec!["click".to_string()])
+        }
+    }
+
+    #[test]
+    fn registered_names_are_always_subscribed() {
+        let subscribed = |events: &[&str]| ServerMessage::Subscribe {
+            events: Some(events.iter().map(|event| event.to_string()).collect()),
+        };
+        let server = Listener::new()
+            .address("127.0.0.1")
+            .port(0)
+            .on("input", |_, _| {})
+            .on("cart:add", |_, _| {})
+            .spawn(Clicks)
+            .unwrap();
+        let mut stream = connect(server.local_addr());
+        assert_eq!(
+            read_message(&mut stream),
+            subscribed(&["cart:add", "click", "input"])
+        );
+        server.shutdown().unwrap();
+        // a router tells the website again when its events change
+        let router = Router::new().on("keydown", |_: Value, _: &crate::Sender| {});
+        let server = Listener::new()
+            .address("127.0.0.1")
+            .port(0)
+            .on("click", |_, _| {})
+            .spawn(router.clone())
+            .unwrap();
+        let mut stream = connect(server.local_addr());
+        assert_eq!(read_message(&mut stream), subscribed(&["click", "keydown"]));
+        router.mount("scroll", |_: Value, _: &crate::Sender| {});
+        assert_eq!(
+            read_message(&mut stream),
+            subscribed(&["click", "keydown", "scroll"])
+        );
+        server.shutdown().unwrap();
+    }
+
+    #[test]
+    fn policies_hold_events_back_before_the_handler() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = seen.clone();
+        let server = Listener::new()
+            .address("127.0.0.1")
+            .port(0)
+            .policy("input", Policy::Debounce(Duration::from_millis(50)))
+            .on("input", move |payload, _ctx| {
+                log.lock().unwrap().push(payload)
+            })
+            .spawn(Handler(Arc::default()))
+            .unwrap();
+        let mut stream = connect(server.local_addr());
+        for i in 0..5 {
+            send_message(
+                &mut stream,
+                &ClientMessage::Event {
+                    name: "input".to_string(),
+                    event: json!(i),
+                },
+            );
+        }
+        wait_until(|| !seen.lock().unwrap().is_empty());
+        thread::sleep(Duration::from_millis(100));
+        assert_eq!(*seen.lock().unwrap(), vec![json!(4)]);
+        server.shutdown().unwrap();
+    }
+
+    #[test]
+    fn one_address_can_only_open_so_many_connections() {
+        let server = Listener::new()
+            .address("127.0.0.1")
+            .port(0)
+            .max_connections_per_ip(1)
+            .spawn(Handler(Arc::default()))
+            .unwrap();
+        let first = connect(server.local_addr());
+        let mut second = request(server.local_addr(), "/");
+        let mut response = String::new();
+        second.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 429"), "{}", response);
+        // the address can connect again once the first connection is gone
+        drop(first);
+        wait_until(|| server.connections() == 0);
+        connect(server.local_addr());
+        server.shutdown().unwrap();
+    }
+
+    #[test]
+    fn connections_over_the_rate_limit_are_closed() {
+        let server = Listener::new()
+            .address("127.0.0.1")
+            .port(0)
+            .rate_limit(RateLimit {
+                messages: 2,
+                bytes: 1024,
+                exceeded: Exceeded::Close,
+            })
+            .spawn(Handler(Arc::default()))
+            .unwrap();
+        let mut stream = connect(server.local_addr());
+        assert_eq!(
+            read_message(&mut stream),
+            ServerMessage::Subscribe { events: None }
+        );
+        for i in 0..3 {
+            send_message(
+                &mut stream,
+                &ClientMessage::Event {
+                    name: "input".to_string(),
+                    event: json!(i),
+                },
+            );
+        }
+        let mut close = [0; 4];
+        stream.read_exact(&mut close).unwrap();
+        assert_eq!(close[0], 0x88);
+        assert_eq!(u16::from_be_bytes([close[2], close[3]]), 1008);
+        server.shutdown().unwrap();
+    }
+
+    #[test]
+    fn connections_know_who_opened_them() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = seen.clone();
+        let server = Listener::new()
+            .address("127.0.0.1")
+            .port(0)
+            .authenticate(
+                |req| match crate::handshake::query(req.resource(), "token").as_deref() {
+                    Some("secret") => Ok(Identity::new("ada")),
+                    Some(_) => Err(Reject::forbidden()),
+                    None => Err(Reject::unauthorized()),
+                },
+            )
+            .on("whoami", move |_, ctx| {
+                log.lock().unwrap().push(crate::identity::identity(ctx))
+            })
+            .spawn(Handler(Arc::default()))
+            .unwrap();
+        for (resource, status) in [("/", "401"), ("/?token=wrong", "403")] {
+            let mut stream = request(server.local_addr(), resource);
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            assert!(response.starts_with(&format!("HTTP/1.1 {}", status)));
+        }
+        let mut stream = connect_to(server.local_addr(), "/?token=secret");
+        assert_eq!(
+            read_message(&mut stream),
+            ServerMessage::Subscribe { events: None }
+        );
+        send_message(
+            &mut stream,
+            &ClientMessage::Event {
+                name: "whoami".to_string(),
+                event: Value::Null,
+            },
+        );
+        wait_until(|| !seen.lock().unwrap().is_empty());
+        assert_eq!(*seen.lock().unwrap(), vec![Some(Identity::new("ada"))]);
+        crate::identity::send_identity(&server.broadcaster(), "ada", JS, "1").unwrap();
+        assert_eq!(
+            read_message(&mut stream),
+            ServerMessage::Js {
+                code: "1".to_string()
+            }
+        );
+        server.shutdown().unwrap();
+    }
+
+    #[test]
+    fn sessions_can_be_resumed_on_a_new_socket() {
+        let remembered: Arc<Mutex<Option<crate::Sender>>> = Arc::default();
+        let remember = remembered.clone();
+        let server = Listener::new()
+            .address("127.0.0.1")
+            .port(0)
+            .resume(Duration::from_secs(10), 1024)
+            .authenticate(|_| Ok(Identity::new("ada")))
+            .on("remember", move |_, ctx| {
+                crate::rooms::join(ctx, "lobby");
+                *remember.lock().unwrap() = Some(ctx.clone());
+            })
+            .spawn(Handler(Arc::default()))
+            .unwrap();
+        let mut stream = connect(server.local_addr());
+        let id = match read_message(&mut stream) {
+            ServerMessage::Session { id, resumed } => {
+                assert!(!resumed);
+                id
+            }
+            message => panic!("{:?}", message),
+        };
+        assert_eq!(
+            read_message(&mut stream),
+            ServerMessage::Subscribe { events: None }
+        );
+        send_message(
+            &mut stream,
+            &ClientMessage::Event {
+                name: "remember".to_string(),
+                event: Value::Null,
+            },
+        );
+        wait_until(|| remembered.lock().unwrap().is_some());
+        let ctx = remembered.lock().unwrap().clone().unwrap();
+        drop(stream);
+        let registry = server.broadcaster.registry().clone();
+        wait_until(|| server.connections() == 0 && registry.parked.lock().unwrap().len() == 1);
+
+        // everything that is sent while the session is parked waits for it
+        crate::send::send(&ctx, JS, "1").unwrap();
+        crate::send::broadcast(&server.broadcaster, JS, "2").unwrap();
+        crate::rooms::send_room(&server.broadcaster, "lobby", JS, "3").unwrap();
+        let mut stream = connect_to(server.local_addr(), &format!("/?razer_session={}", id));
+        for code in ["1", "2", "3"] {
+            assert_eq!(
+                read_message(&mut stream),
+                ServerMessage::Js {
+                    code: code.to_string()
+                }
+            );
+        }
+        assert_eq!(
+            read_message(&mut stream),
+            ServerMessage::Session {
+                id: id.clone(),
+                resumed: true
+            }
+        );
+        // the old context now sends to the new socket and the room was joined again
+        crate::send::send(&ctx, JS, "4").unwrap();
+        assert_eq!(
+            read_message(&mut stream),
+            ServerMessage::Js {
+                code: "4".to_string()
+            }
+        );
+        assert!(registry.parked.lock().unwrap().is_empty());
+        assert_eq!(crate::rooms::members(&server.broadcaster, "lobby").len(), 1);
+        // and it is the new socket for everything else too
+        assert!(crate::connections::open(&ctx));
+        assert!(crate::rooms::is_member(&ctx, "lobby"));
+        assert_eq!(crate::identity::identity(&ctx), Some(Identity::new("ada")));
+        crate::rooms::join(&ctx, "game");
+        assert_eq!(crate::rooms::rooms_of(&ctx).len(), 2);
+        crate::rooms::send_room(&server.broadcaster, "game", JS, "5").unwrap();
+        assert_eq!(
+            read_message(&mut stream),
+            ServerMessage::Js {
+                code: "5".to_string()
+            }
+        );
+        // a session can only be resumed once
+        let mut other = connect_to(server.local_addr(), &format!("/?razer_session={}", id));
+        assert!(matches!(
+            read_message(&mut other),
+            ServerMessage::Session { resumed: false, .. }
+        ));
+        server.shutdown().unwrap();
+    }
+}
@@ -0,0 +1,413 @@
+use std::collections::VecDeque;
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+
+use log::{debug, error};
+
+/// What to do with a new event when a connection already has `Listener::queue_depth` events
+/// waiting <br />
+/// only dom and custom events are ever dropped, a connection always sees `on_connect` and
+/// `on_disconnect`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    /// Drop the new event
+    Drop,
+    /// Drop the oldest event that is waiting and keep the new one
+    DropOldest,
+    /// If an event with the same name is waiting it is dropped and the new one goes to the back of
+    /// the queue, otherwise the new event is dropped <br />
+    /// this is good for events like `mousemove` or `scroll` where only the last one matters
+    Coalesce,
+}
+
+// how many jobs one connection can run before the worker moves on to the next connection
+const BATCH: usize = 32;
+
+type Task = Box<dyn FnOnce() + Send>;
+type Handle<S, J> = Box<dyn Fn(&mut S, J) + Send + Sync>;
+
+/// A fixed number of threads that run the events of every connection
+pub(crate) struct Pool {
+    tasks: mpsc::Sender<Task>,
+}
+
+impl Pool {
+    /// This fails if a thread can not be started
+    pub(crate) fn new(workers: usize) -> io::Result<Arc<Pool>> {
+        let (tasks, receiver) = mpsc::channel::<Task>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for i in 0..workers.max(1) {
+            let receiver = receiver.clone();
+            thread::Builder::new()
+                .name(format!("razer-worker-{}", i))
+                .spawn(move || loop {
+                    // the lock is only held while waiting so the other workers can run tasks
+                    let task = match receiver.lock().unwrap().recv() {
+                        Ok(task) => task,
+                        Err(_) => return,
+                    };
+                    task();
+                })?;
+        }
+        Ok(Arc::new(Pool { tasks }))
+    }
+
+    fn execute(&self, task: Task) {
+        let _ = self.tasks.send(task);
+    }
+}
+
+/// Something that can be put in a `Mailbox`
+pub(crate) trait Work: Send + 'static {
+    /// The name of the event if it can be dropped when the queue is full
+    fn name(&self) -> Option<&str>;
+
+    /// The job that ends the session, it is still run after the session panicked so it can clean
+    /// up
+    fn last(&self) -> bool {
+        false
+    }
+}
+
+struct State<S, J> {
+    session: Option<S>,
+    jobs: VecDeque<J>,
+    running: bool,
+    panicked: bool,
+}
+
+/// The queue of one connection, at most one worker runs its jobs at a time so they are handled in
+/// the order they were pushed
+pub(crate) struct Mailbox<S, J> {
+    state: Mutex<State<S, J>>,
+    pool: Arc<Pool>,
+    depth: usize,
+    overflow: Overflow,
+    handle: Handle<S, J>,
+}
+
+impl<S: Send + 'static, J: Work> Mailbox<S, J> {
+    pub(crate) fn new<H>(
+        pool: Arc<Pool>,
+        session: S,
+        depth: usize,
+        overflow: Overflow,
+        handle: H,
+    ) -> Arc<Mailbox<S, J>>
+    where
+        H: Fn(&mut S, J) + Send + Sync + 'static,
+    {
+        Arc::new(Mailbox {
+            state: Mutex::new(State {
+                session: Some(session),
+                jobs: VecDeque::new(),
+                running: false,
+                panicked: false,
+            }),
+            pool,
+            depth,
+            overflow,
+            handle: Box::new(handle),
+        })
+    }
+
+    pub(crate) fn push(self: &Arc<Self>, job: J) {
+        let mut state = self.state.lock().unwrap();
+        if state.jobs.len() >= self.depth {
+            if let Some(name) = job.name() {
+                let dropped = match self.overflow {
+                    Overflow::Drop => None,
+                    Overflow::DropOldest => state.jobs.iter().position(|job| job.name().is_some()),
+                    Overflow::Coalesce => state
+                        .jobs
+                        .iter()
+                        .rposition(|waiting| waiting.name() == Some(name)),
+                };
+                match dropped {
+                    Some(dropped) => {
+                        state.jobs.remove(dropped);
+                    }
+                    None => {
+                        debug!("the queue is full, dropping a {} event", name);
+                        return;
+                    }
+                }
+            }
+        }
+        state.jobs.push_back(job);
+        if !state.running {
+            state.running = true;
+            drop(state);
+            self.schedule();
+        }
+    }
+
+    /// Like `push` but an event with the same name that is still waiting is dropped first, so
+    /// only the newest one is handled
+    pub(crate) fn push_latest(self: &Arc<Self>, job: J) {
+        if let Some(name) = job.name() {
+            let mut state = self.state.lock().unwrap();
+            if let Some(waiting) = state
+                .jobs
+                .iter()
+                .rposition(|waiting| waiting.name() == Some(name))
+            {
+                state.jobs.remove(waiting);
+            }
+        }
+        self.push(job);
+    }
+
+    fn schedule(self: &Arc<Self>) {
+        let mailbox = self.clone();
+        self.pool.execute(Box::new(move || mailbox.run()));
+    }
+
+    fn run(self: Arc<Self>) {
+        for _ in 0..BATCH {
+            // the session is taken out so the lock is not held while the job runs and new jobs
+            // can be pushed
+            let (job, session) = {
+                let mut state = self.state.lock().unwrap();
+                match state.jobs.pop_front() {
+                    Some(job) if state.panicked && !job.last() => continue,
+                    Some(job) => (job, state.session.take()),
+                    None => {
+                        state.running = false;
+                        return;
+                    }
+                }
+            };
+            let mut session = match session {
+                Some(session) => session,
+                None => continue,
+            };
+            let ran = panic::catch_unwind(AssertUnwindSafe(|| (self.handle)(&mut session, job)));
+            let mut state = self.state.lock().unwrap();
+            if ran.is_err() && !state.panicked {
+                error!("a session panicked, only the job that ends it will still be run");
+                state.panicked = true;
+            }
+            state.session = Some(session);
+        }
+        // go to the back of the line so one busy connection can not keep a worker to itself
+        self.schedule();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Barrier;
+    use std::time::Duration;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    enum Job {
+        Event(&'static str, u32),
+        Close,
+    }
+
+    impl Work for Job {
+        fn name(&self) -> Option<&str> {
+            match self {
+                Job::Event(name, _) => Some(name),
+                Job::Close => None,
+            }
+        }
+
+        fn last(&self) -> bool {
+            *self == Job::Close
+        }
+    }
+
+    type Seen = Arc<Mutex<Vec<Job>>>;
+
+    fn mailbox(
+        pool: &Arc<Pool>,
+        depth: usize,
+        overflow: Overflow,
+    ) -> (Arc<Mailbox<(), Job>>, Seen) {
+        let seen = Seen::default();
+        let log = seen.clone();
+        let mailbox = Mailbox::new(pool.clone(), (), depth, overflow, move |_, job| {
+            log.lock().unwrap().push(job)
+        });
+        (mailbox, seen)
+    }
+
+    fn wait_until(done: impl Fn() -> bool) {
+        for _ in 0..500 {
+            if done() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("timed out");
+    }
+
+    // blocks the only worker so jobs pile up in the queue
+    fn block(pool: &Arc<Pool>) -> Arc<Barrier> {
+        let barrier = Arc::new(Barrier::new(2));
+        let wait = barrier.clone();
+        pool.execute(Box::new(move || {
+            wait.wait();
+        }));
+        barrier
+    }
+
+    #[test]
+    fn jobs_of_one_connection_are_in_order() {
+        let pool = Pool::new(8).unwrap();
+        let (mailbox, seen) = mailbox(&pool, usize::MAX, Overflow::Drop);
+        for i in 0..1000 {
+            mailbox.push(Job::Event(if i % 2 == 0 { "keydown" } else { "keyup" }, i));
+        }
+        wait_until(|| seen.lock().unwrap().len() == 1000);
+        let seen = seen.lock().unwrap();
+        for (i, job) in seen.iter().enumerate() {
+            match job {
+                Job::Event(_, n) => assert_eq!(*n as usize, i),
+                Job::Close => panic!(),
+            }
+        }
+    }
+
+    #[test]
+    fn connections_run_in_parallel() {
+        let pool = Pool::new(2).unwrap();
+        let barrier = Arc::new(Barrier::new(2));
+        let done = Arc::new(Mutex::new(0));
+        let mut mailboxes = Vec::new();
+        for _ in 0..2 {
+            let barrier = barrier.clone();
+            let done = done.clone();
+            // each connection waits for the other one, so this only finishes if they run at once
+            mailboxes.push(Mailbox::new(
+                pool.clone(),
+                (),
+                8,
+                Overflow::Drop,
+                move |_, _: Job| {
+                    barrier.wait();
+                    *done.lock().unwrap() += 1;
+                },
+            ));
+        }
+        for mailbox in &mailboxes {
+            mailbox.push(Job::Close);
+        }
+        wait_until(|| *done.lock().unwrap() == 2);
+    }
+
+    #[test]
+    fn full_queues_drop_the_new_event() {
+        let pool = Pool::new(1).unwrap();
+        let barrier = block(&pool);
+        let (mailbox, seen) = mailbox(&pool, 2, Overflow::Drop);
+        mailbox.push(Job::Event("mousemove", 0));
+        mailbox.push(Job::Event("mousemove", 1));
+        mailbox.push(Job::Event("click", 2));
+        mailbox.push(Job::Close);
+        barrier.wait();
+        wait_until(|| seen.lock().unwrap().len() == 3);
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![
+                Job::Event("mousemove", 0),
+                Job::Event("mousemove", 1),
+                Job::Close
+            ]
+        );
+    }
+
+    #[test]
+    fn full_queues_can_drop_the_oldest_event() {
+        let pool = Pool::new(1).unwrap();
+        let barrier = block(&pool);
+        let (mailbox, seen) = mailbox(&pool, 2, Overflow::DropOldest);
+        mailbox.push(Job::Event("mousemove", 0));
+        mailbox.push(Job::Event("mousemove", 1));
+        mailbox.push(Job::Event("click", 2));
+        barrier.wait();
+        wait_until(|| seen.lock().unwrap().len() == 2);
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![Job::Event("mousemove", 1), Job::Event("click", 2)]
+        );
+    }
+
+    #[test]
+    fn full_queues_can_coalesce_events() {
+        let pool = Pool::new(1).unwrap();
+        let barrier = block(&pool);
+        let (mailbox, seen) = mailbox(&pool, 3, Overflow::Coalesce);
+        mailbox.push(Job::Event("mousemove", 0));
+        mailbox.push(Job::Event("keydown", 1));
+        mailbox.push(Job::Event("mousemove", 2));
+        mailbox.push(Job::Event("mousemove", 3));
+        mailbox.push(Job::Event("keyup", 4));
+        mailbox.push(Job::Close);
+        barrier.wait();
+        wait_until(|| seen.lock().unwrap().len() == 4);
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![
+                Job::Event("mousemove", 0),
+                Job::Event("keydown", 1),
+                Job::Event("mousemove", 3),
+                Job::Close
+            ]
+        );
+    }
+
+    #[test]
+    fn only_the_latest_waiting_event_is_kept() {
+        let pool = Pool::new(1).unwrap();
+        let barrier = block(&pool);
+        let (mailbox, seen) = mailbox(&pool, usize::MAX, Overflow::Drop);
+        mailbox.push_latest(Job::Event("scroll", 0));
+        mailbox.push(Job::Event("click", 1));
+        mailbox.push_latest(Job::Event("scroll", 2));
+        mailbox.push_latest(Job::Event("scroll", 3));
+        barrier.wait();
+        wait_until(|| seen.lock().unwrap().len() == 2);
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![Job::Event("click", 1), Job::Event("scroll", 3)]
+        );
+    }
+
+    #[test]
+    fn a_panic_only_stops_its_own_connection() {
+        let pool = Pool::new(1).unwrap();
+        let broken = Mailbox::new(pool.clone(), (), 8, Overflow::Drop, |_, _: Job| {
+            panic!("oops")
+        });
+        broken.push(Job::Close);
+        broken.push(Job::Close);
+        let (mailbox, seen) = mailbox(&pool, 8, Overflow::Drop);
+        mailbox.push(Job::Close);
+        wait_until(|| seen.lock().unwrap().len() == 1);
+    }
+
+    #[test]
+    fn a_session_that_panicked_is_still_closed() {
+        let pool = Pool::new(1).unwrap();
+        let seen = Seen::default();
+        let log = seen.clone();
+        let mailbox = Mailbox::new(pool, (), 8, Overflow::Drop, move |_, job: Job| {
+            if job == Job::Event("click", 0) {
+                panic!("oops");
+            }
+            log.lock().unwrap().push(job)
+        });
+        mailbox.push(Job::Event("click", 0));
+        mailbox.push(Job::Event("click", 1));
+        mailbox.push(Job::Close);
+        wait_until(|| seen.lock().unwrap().len() == 1);
+        assert_eq!(*seen.lock().unwrap(), vec![Job::Close]);
+    }
+}
@@ -0,0 +1,370 @@
+use std::error::Error;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::dom::Command;
+use crate::eval::JsException;
+use crate::event::event_type::Event;
+use crate::vdom::Patch;
+
+/// The version of the protocol that this version of razer and its script speak <br />
+/// every frame carries it in the `v` field and frames with a different version are rejected
+pub const VERSION: u32 = 1;
+
+/// Everything the server can send to the website
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    /// Run the code on the website
+    Js { code: String },
+    /// Set the body of the website
+    Html { html: String },
+    /// Run the code and send back what it returns, if there is no `id` the website will reply
+    /// with `ClientMessage::Eval` instead of `ClientMessage::EvalResult`
+    Eval {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<u64>,
+        code: String,
+    },
+    /// Change the website without running any javascript
+    Dom { commands: Vec<Command> },
+    /// Change the view inside of the first element that matches `root`, see `razer::vdom`
+    Patch { root: String, patches: Vec<Patch> },
+    /// Only send these dom events from now on, `None` means every event, see
+    /// `razer::event::subscription`
+    Subscribe { events: Option<Vec<String>> },
+    /// The id of the session, the website sends it back when it opens a new socket so it gets the
+    /// same session, `resumed` is false for a new session, see `Listener::resume`
+    Session { id: String, resumed: bool },
+}
+
+/// Everything the website can send to the server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// A dom event like `click`
+    Event {
+        name: String,
+        #[serde(default)]
+        event: Value,
+    },
+    /// The reply to a `ServerMessage::Eval` without an id
+    Eval {
+        #[serde(default)]
+        result: Value,
+        code: String,
+    },
+    /// The reply to a `ServerMessage::Eval` with an id
+    EvalResult {
+        id: u64,
+        #[serde(default)]
+        result: Value,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<JsException>,
+    },
+}
+
+/// Why a frame could not be read
+#[derive(Debug)]
+pub enum ProtocolError {
+    /// The frame is not valid json or does not look like a message
+    Json(serde_json::Error),
+    /// The frame was made for a different version of the protocol
+    Version(u32),
+}
+
+impl fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProtocolError::Json(err) => write!(f, "invalid frame: {}", err),
+            ProtocolError::Version(version) => write!(
+                f,
+                "the frame is for protocol version {} but razer speaks {}",
+                version, VERSION
+            ),
+        }
+    }
+}
+
+impl Error for ProtocolError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ProtocolError::Json(err) => Some(err),
+            ProtocolError::Version(_) => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for ProtocolError {
+    fn from(err: serde_json::Error) -> Self {
+        ProtocolError::Json(err)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Frame<M> {
+    v: u32,
+    #[serde(flatten)]
+    message: M,
+}
+
+fn encode<M: Serialize>(message: &M) -> String {
+    // every message is plain strings and json values so this can not fail
+    serde_json::to_string(&Frame {
+        v: VERSION,
+        message,
+    })
+    .expect("a razer message is always valid json")
+}
+
+fn decode<M: for<'de> Deserialize<'de>>(frame: &str) -> Result<M, ProtocolError> {
+    let frame: Frame<Value> = serde_json::from_str(frame)?;
+    if frame.v != VERSION {
+        return Err(ProtocolError::Version(frame.v));
+    }
+    Ok(serde_json::from_value(frame.message)?)
+}
+
+impl ServerMessage {
+    /// Turn the old `Event` and a string into a message
+    pub fn new(event: Event, data: &str) -> Self {
+        match event {
+            Event::JS => ServerMessage::Js {
+                code: data.to_string(),
+            },
+            Event::HTML => ServerMessage::Html {
+                html: data.to_string(),
+            },
+            Event::EVAL => ServerMessage::Eval {
+                id: None,
+                code: data.to_string(),
+            },
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        encode(self)
+    }
+
+    pub fn decode(frame: &str) -> Result<Self, ProtocolError> {
+        decode(frame)
+    }
+}
+
+impl ClientMessage {
+    pub fn encode(&self) -> String {
+        encode(self)
+    }
+
+    pub fn decode(frame: &str) -> Result<Self, ProtocolError> {
+        decode(frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    const HOSTILE: &[&str] = &[
+        "",
+        "\"",
+        "\\",
+        "\\\"",
+        "\"}, {\"type\": \"js\", \"code\": \"alert(1)\"}",
+        "line\nbreak\rreturn\ttab",
+        "\u{0}\u{1}\u{1f}\u{7f}",
+        "</script><script>alert(1)</script>",
+        "\u{2028}\u{2029}",
+        "emoji 🦀 and ünïcödé",
+        "{{}}[[]]",
+    ];
+
+    #[test]
+    fn server_messages_round_trip() {
+        for payload in HOSTILE {
+            let messages = vec![
+                ServerMessage::Js {
+                    code: payload.to_string(),
+                },
+                ServerMessage::Html {
+                    html: payload.to_string(),
+                },
+                ServerMessage::Eval {
+                    id: Some(7),
+                    code: payload.to_string(),
+                },
+                ServerMessage::Eval {
+                    id: None,
+                    code: payload.to_string(),
+                },
+                ServerMessage::Subscribe {
+                    events: Some(vec![payload.to_string()]),
+                },
+                ServerMessage::Subscribe { events: None },
+                ServerMessage::Session {
+                    id: payload.to_string(),
+                    resumed: true,
+                },
+            ];
+            for message in messages {
+                let frame = message.encode();
+                assert!(serde_json::from_str::<Value>(&frame).is_ok(), "{}", frame);
+                assert_eq!(ServerMessage::decode(&frame).unwrap(), message);
+            }
+        }
+    }
+
+    #[test]
+    fn dom_commands_round_trip() {
+        use crate::dom::Position;
+        for payload in HOSTILE {
+            let message = ServerMessage::Dom {
+                commands: vec![
+                    Command::set_text(payload, payload),
+                    Command::set_html(payload, payload),
+                    Command::set_attribute(payload, payload, payload),
+                    Command::insert(payload, Position::AfterEnd, payload),
+                    Command::set_style(payload, payload, payload),
+                    Command::focus(payload),
+                ],
+            };
+            assert_eq!(ServerMessage::decode(&message.encode()).unwrap(), message);
+        }
+    }
+
+    #[test]
+    fn patches_round_trip() {
+        use crate::vdom::Node;
+        for payload in HOSTILE {
+            let node = Node::element("p")
+                .attr(payload, payload)
+                .child(Node::text(payload));
+            let message = ServerMessage::Patch {
+                root: payload.to_string(),
+                patches: vec![
+                    Patch::Replace {
+                        path: vec![],
+                        node: node.clone(),
+                    },
+                    Patch::Append {
+                        path: vec![0, 1],
+                        node,
+                    },
+                    Patch::SetText {
+                        path: vec![2],
+                        text: payload.to_string(),
+                    },
+                ],
+            };
+            assert_eq!(ServerMessage::decode(&message.encode()).unwrap(), message);
+        }
+    }
+
+    #[test]
+    fn client_messages_round_trip() {
+        for payload in HOSTILE {
+            let messages = vec![
+                ClientMessage::Event {
+                    name: payload.to_string(),
+                    event: json!({ "key": payload, "target": { "id": payload } }),
+                },
+                ClientMessage::Eval {
+                    result: json!(payload),
+                    code: payload.to_string(),
+                },
+                ClientMessage::EvalResult {
+                    id: u64::MAX,
+                    result: json!([payload, { "nested": payload }]),
+                    error: None,
+                },
+                ClientMessage::EvalResult {
+                    id: 0,
+                    result: Value::Null,
+                    error: Some(JsException {
+                        name: payload.to_string(),
+                        message: payload.to_string(),
+                        stack: Some(payload.to_string()),
+                    }),
+                },
+            ];
+            for message in messages {
+                assert_eq!(ClientMessage::decode(&message.encode()).unwrap(), message);
+            }
+        }
+    }
+
+    #[test]
+    fn frames_carry_the_version() {
+        let frame: Value = serde_json::from_str(
+            &ServerMessage::Js {
+                code: "1".to_string(),
+            }
+            .encode(),
+        )
+        .unwrap();
+        assert_eq!(frame, json!({ "v": VERSION, "type": "js", "code": "1" }));
+    }
+
+    #[test]
+    fn the_old_events_map_to_messages() {
+        assert_eq!(
+            ServerMessage::new(Event::HTML, "<p>\"hi\"</p>"),
+            ServerMessage::Html {
+                html: "<p>\"hi\"</p>".to_string()
+            }
+        );
+        assert_eq!(
+            ServerMessage::new(Event::EVAL, "1 + 1"),
+            ServerMessage::Eval {
+                id: None,
+                code: "1 + 1".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn malformed_frames_are_errors() {
+        let frames = [
+            "",
+            "null",
+            "[]",
+            "\"event\"",
+            "{",
+            "{\"v\": 1}",
+            "{\"v\": 1, \"type\": \"nope\"}",
+            "{\"v\": 1, \"type\": \"event\"}",
+            "{\"v\": 1, \"type\": \"event\", \"name\": 5}",
+            "{\"v\": 1, \"type\": \"eval_result\", \"id\": -1}",
+            "{\"v\": \"1\", \"type\": \"event\", \"name\": \"click\"}",
+            "{\"type\": \"event\", \"name\": \"click\"}",
+            "{\"event_name\": \"click\", \"event\": {}}",
+            "\u{0}\u{0}\u{0}",
+        ];
+        for frame in frames {
+            assert!(ClientMessage::decode(frame).is_err(), "{}", frame);
+        }
+    }
+
+    #[test]
+    fn other_versions_are_rejected() {
+        match ClientMessage::decode("{\"v\": 2, \"type\": \"event\", \"name\": \"click\"}") {
+            Err(ProtocolError::Version(2)) => {}
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn events_without_a_payload_still_decode() {
+        assert_eq!(
+            ClientMessage::decode("{\"v\": 1, \"type\": \"event\", \"name\": \"click\"}").unwrap(),
+            ClientMessage::Event {
+                name: "click".to_string(),
+                event: Value::Null,
+            }
+        );
+    }
+}
@@ -0,0 +1,33 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::atomic::AtomicBool;
+use std::sync::Mutex;
+
+use crate::eval::Pending;
+use crate::identity::Identity;
+use crate::resume::Away;
+
+// token -> connection id, the id is kept so a token that was given to a new connection is not
+// sent the messages of the old one
+pub(crate) type Members = HashMap<usize, u32>;
+
+/// What one server knows about its connections, every `Sender` of the server shares it so two
+/// servers in one program never see each other's connections
+#[derive(Default)]
+pub(crate) struct Registry {
+    pub(crate) connections: Mutex<Members>,
+    pub(crate) rooms: Mutex<HashMap<String, Members>>,
+    pub(crate) evals: Mutex<HashMap<u64, Pending>>,
+    // token -> connection id and who opened it, only kept with `Listener::authenticate`
+    pub(crate) identities: Mutex<HashMap<usize, (u32, Identity)>>,
+    // how many connections every address has open, only kept with `Listener::max_connections_per_ip`
+    pub(crate) addresses: Mutex<HashMap<IpAddr, usize>>,
+    // session id -> the session, for the ones whose socket dropped with `Listener::resume`, this
+    // is always locked before `connections`, `rooms` and `identities` so a session that moves
+    // between them is always in one
+    pub(crate) parked: Mutex<HashMap<String, Away>>,
+    // the names given to `Listener::on`, the website is always told to send them
+    pub(crate) custom: Vec<String>,
+    // set by `ServerHandle::shutdown_graceful`, no socket is opened, resumed or parked after it
+    pub(crate) shutting_down: AtomicBool,
+}
@@ -0,0 +1,478 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Write;
+use std::io;
+use std::sync::{Arc, Mutex, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use log::debug;
+
+use crate::error::{Error, Result};
+use crate::identity::Identity;
+use crate::registry::Registry;
+use crate::throttle::Clock;
+
+/// The query parameter the website sends its session id in when it opens the socket again
+pub(crate) const SESSION_PARAM: &str = "razer_session";
+
+/// How long a session waits for its website to come back and how many bytes of messages it keeps
+/// for it, set with `Listener::resume`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Resume {
+    pub(crate) ttl: Duration,
+    pub(crate) buffer: usize,
+}
+
+enum Route {
+    Open,
+    // the socket dropped, the messages wait here until the website comes back
+    Parked {
+        buffer: VecDeque<String>,
+        bytes: usize,
+        max: usize,
+    },
+    Closed,
+}
+
+struct State {
+    out: razer_ws::Sender,
+    route: Route,
+}
+
+/// The socket a session sends to, when the website opens a new socket the session moves over to
+/// it and every `Sender` of the session sends to the new one
+pub(crate) struct Link {
+    id: Option<String>,
+    // the token and connection id of the first socket
+    first: (usize, u32),
+    registry: Arc<Registry>,
+    state: Mutex<State>,
+}
+
+impl Link {
+    pub(crate) fn new(
+        out: razer_ws::Sender,
+        registry: Arc<Registry>,
+        id: Option<String>,
+    ) -> Arc<Link> {
+        Arc::new(Link {
+            id,
+            first: (out.token().0, out.connection_id()),
+            registry,
+            state: Mutex::new(State {
+                out,
+                route: Route::Open,
+            }),
+        })
+    }
+
+    /// The session id, this is only set with `Listener::resume`
+    pub(crate) fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    /// The token and connection id of the socket the session started on, they stay the same when
+    /// it is resumed
+    pub(crate) fn first(&self) -> (usize, u32) {
+        self.first
+    }
+
+    /// The token and connection id of the socket the session is on now
+    pub(crate) fn socket(&self) -> (usize, u32) {
+        let state = self.state.lock().unwrap();
+        (state.out.token().0, state.out.connection_id())
+    }
+
+    /// The sender for the socket the session is on now
+    pub(crate) fn sender(self: &Arc<Self>) -> crate::Sender {
+        let out = self.state.lock().unwrap().out.clone();
+        crate::Sender::new(out, self.registry.clone()).with_link(self.clone())
+    }
+
+    pub(crate) fn send(&self, frame: String) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let State { out, route } = &mut *state;
+        match route {
+            Route::Open => Ok(out.send(frame)?),
+            Route::Parked { buffer, bytes, max } => {
+                if *bytes + frame.len() > *max {
+                    // the website would miss messages, so it gets a new session instead
+                    debug!("the buffer of a parked session is full, it can not be resumed");
+                    *route = Route::Closed;
+                    return Err(Error::Closed);
+                }
+                *bytes += frame.len();
+                buffer.push_back(frame);
+                Ok(())
+            }
+            Route::Closed => Err(Error::Closed),
+        }
+    }
+
+    /// Keep up to `max` bytes of messages until `reopen`
+    pub(crate) fn park(&self, max: usize) {
+        let mut state = self.state.lock().unwrap();
+        if let Route::Open = state.route {
+            state.route = Route::Parked {
+                buffer: VecDeque::new(),
+                bytes: 0,
+                max,
+            };
+        }
+    }
+
+    /// Move the session to a new socket and send it everything that was kept, this is false if
+    /// the session can not be resumed
+    pub(crate) fn reopen(&self, new: razer_ws::Sender) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let buffer = match &mut state.route {
+            Route::Parked { buffer, .. } => std::mem::take(buffer),
+            _ => return false,
+        };
+        state.out = new;
+        state.route = Route::Open;
+        // the lock is held so nothing new is sent before the old messages
+        for frame in buffer {
+            if let Err(err) = state.out.send(frame) {
+                debug!(
+                    "could not send a kept message to {:?}: {}",
+                    state.out.token(),
+                    err
+                );
+                break;
+            }
+        }
+        true
+    }
+
+    pub(crate) fn close(&self) {
+        self.state.lock().unwrap().route = Route::Closed;
+    }
+
+    fn is_closed(&self) -> bool {
+        matches!(self.state.lock().unwrap().route, Route::Closed)
+    }
+}
+
+/// What the rest of the server has to know about a session whose socket dropped, it is kept in
+/// the `Registry` so the messages that are sent to it are kept until it is back
+#[derive(Clone)]
+pub(crate) struct Away {
+    pub(crate) link: Arc<Link>,
+    /// The rooms it was in, it joins them again when it is resumed
+    pub(crate) rooms: Vec<String>,
+    pub(crate) identity: Option<Identity>,
+    /// The token and connection id of the socket that dropped, `send_to` reaches the session with
+    /// the token until a new connection is given it
+    pub(crate) socket: Option<(usize, u32)>,
+}
+
+/// A parked session that was taken to be resumed
+pub(crate) struct Parked<M> {
+    pub(crate) mailbox: M,
+    pub(crate) away: Away,
+}
+
+type End<M> = Box<dyn Fn(M) + Send + Sync>;
+
+/// Every parked session of one server by its id, `end` is called for the ones that are not
+/// resumed in time
+pub(crate) struct Sessions<M> {
+    // the mailboxes and when they end, the rest of the session is in `Registry::parked`
+    waiting: Mutex<HashMap<String, (M, Instant)>>,
+    registry: Arc<Registry>,
+    clock: Arc<dyn Clock>,
+    end: End<M>,
+}
+
+impl<M> Sessions<M> {
+    pub(crate) fn new(
+        registry: Arc<Registry>,
+        clock: Arc<dyn Clock>,
+        end: impl Fn(M) + Send + Sync + 'static,
+    ) -> Sessions<M> {
+        Sessions {
+            waiting: Mutex::new(HashMap::new()),
+            registry,
+            clock,
+            end: Box::new(end),
+        }
+    }
+
+    /// Keep the session until `ttl` is over, `leave` takes the connection out of the registry
+    /// while the parked sessions are locked so a message that is sent at the same time either
+    /// goes to the socket or is kept for the session
+    pub(crate) fn park(&self, id: &str, mailbox: M, ttl: Duration, leave: impl FnOnce() -> Away) {
+        let until = self.clock.now() + ttl;
+        let mut waiting = self.waiting.lock().unwrap();
+        let mut parked = self.registry.parked.lock().unwrap();
+        parked.insert(id.to_string(), leave());
+        waiting.insert(id.to_string(), (mailbox, until));
+    }
+
+    /// The session with this id if it can still be resumed by `identity`, it still gets its
+    /// messages kept until `resume`
+    pub(crate) fn take(&self, id: &str, identity: Option<&Identity>) -> Option<Parked<M>> {
+        let mut waiting = self.waiting.lock().unwrap();
+        let away = {
+            let parked = self.registry.parked.lock().unwrap();
+            let away = parked.get(id)?;
+            // someone else can not take over the session even if they have its id
+            if away.identity.as_ref() != identity {
+                return None;
+            }
+            away.clone()
+        };
+        let (mailbox, until) = waiting.remove(id)?;
+        drop(waiting);
+        let session = Parked { mailbox, away };
+        if until <= self.clock.now() || session.away.link.is_closed() {
+            self.finish(session);
+            return None;
+        }
+        Some(session)
+    }
+
+    /// A new connection with `token` is opened, `join` puts it in the registry while the parked
+    /// sessions are locked
+    pub(crate) fn open(&self, token: usize, join: impl FnOnce()) {
+        let mut parked = self.registry.parked.lock().unwrap();
+        claim(&mut parked, token);
+        join();
+    }
+
+    /// The session is on its new socket with `token`, `join` puts the connection back in the
+    /// registry while the parked sessions are locked
+    pub(crate) fn resume(&self, session: &Parked<M>, token: usize, join: impl FnOnce()) {
+        let mut parked = self.registry.parked.lock().unwrap();
+        claim(&mut parked, token);
+        join();
+        if let Some(id) = session.away.link.id() {
+            parked.remove(id);
+        }
+    }
+
+    /// End every session that was not resumed in time
+    pub(crate) fn sweep(&self) {
+        let now = self.clock.now();
+        let expired: Vec<Parked<M>> = {
+            let mut waiting = self.waiting.lock().unwrap();
+            let parked = self.registry.parked.lock().unwrap();
+            let ids: Vec<String> = waiting
+                .iter()
+                .filter(|(id, (_, until))| {
+                    *until <= now || parked.get(*id).is_none_or(|away| away.link.is_closed())
+                })
+                .map(|(id, _)| id.clone())
+                .collect();
+            ids.iter()
+                .filter_map(|id| {
+                    let (mailbox, _) = waiting.remove(id)?;
+                    let away = parked.get(id)?.clone();
+                    Some(Parked { mailbox, away })
+                })
+                .collect()
+        };
+        for session in expired {
+            self.finish(session);
+        }
+    }
+
+    pub(crate) fn finish(&self, session: Parked<M>) {
+        let link = &session.away.link;
+        if let Some(id) = link.id() {
+            self.registry.parked.lock().unwrap().remove(id);
+        }
+        link.close();
+        // the evals waited in case the website came back
+        crate::eval::close(&link.sender());
+        (self.end)(session.mailbox);
+    }
+}
+
+impl<M: Send + 'static> Sessions<M> {
+    /// Call `sweep` every `every` until the server is gone, this fails if the thread can not be
+    /// started
+    pub(crate) fn sweep_every(self: &Arc<Self>, every: Duration) -> io::Result<()> {
+        let sessions: Weak<Sessions<M>> = Arc::downgrade(self);
+        thread::Builder::new()
+            .name("razer-sessions".to_string())
+            .spawn(move || loop {
+                thread::sleep(every);
+                match sessions.upgrade() {
+                    Some(sessions) => sessions.sweep(),
+                    None => return,
+                }
+            })?;
+        Ok(())
+    }
+}
+
+// the sessions that are still parked when the server stops are ended too
+impl<M> Drop for Sessions<M> {
+    fn drop(&mut self) {
+        let waiting: Vec<(String, M)> = self
+            .waiting
+            .get_mut()
+            .unwrap()
+            .drain()
+            .map(|(id, (mailbox, _))| (id, mailbox))
+            .collect();
+        for (id, mailbox) in waiting {
+            let away = self.registry.parked.lock().unwrap().get(&id).cloned();
+            match away {
+                Some(away) => self.finish(Parked { mailbox, away }),
+                None => (self.end)(mailbox),
+            }
+        }
+    }
+}
+
+/// A new session id, it is random so it can not be guessed
+// the token belongs to a new connection now, so `send_to` with it no longer reaches a parked
+// session that had it
+fn claim(parked: &mut HashMap<String, Away>, token: usize) {
+    for away in parked.values_mut() {
+        if away.socket.is_some_and(|(old, _)| old == token) {
+            away.socket = None;
+        }
+    }
+}
+
+pub(crate) fn new_id() -> Result<String> {
+    let mut bytes = [0; 16];
+    openssl::rand::rand_bytes(&mut bytes)?;
+    Ok(bytes.iter().fold(String::new(), |mut id, byte| {
+        let _ = write!(id, "{:02x}", byte);
+        id
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use razer_ws::util::Token;
+
+    use super::*;
+    use crate::event::event_type::Event::JS;
+    use crate::throttle::FakeClock;
+
+    type Ended = Arc<Mutex<Vec<u32>>>;
+
+    fn sessions() -> (Sessions<u32>, Arc<FakeClock>, Ended) {
+        let clock = Arc::new(FakeClock::default());
+        let ended = Ended::default();
+        let log = ended.clone();
+        let sessions = Sessions::new(Arc::default(), clock.clone(), move |mailbox| {
+            log.lock().unwrap().push(mailbox)
+        });
+        (sessions, clock, ended)
+    }
+
+    fn link(id: &str) -> Arc<Link> {
+        // parked links only buffer, so the socket is never used
+        let (_socket, out) = crate::sender::detached(Arc::default(), 0, 0);
+        let link = Link::new((*out).clone(), Arc::default(), Some(id.to_string()));
+        link.park(1024);
+        link
+    }
+
+    fn park(sessions: &Sessions<u32>, id: &str, mailbox: u32, identity: Option<Identity>) {
+        let away = Away {
+            link: link(id),
+            rooms: Vec::new(),
+            identity,
+            socket: Some((0, 0)),
+        };
+        sessions.park(id, mailbox, Duration::from_secs(10), || away);
+    }
+
+    #[test]
+    fn sessions_are_kept_until_the_ttl_is_over() {
+        let (sessions, clock, ended) = sessions();
+        park(&sessions, "a", 1, None);
+        park(&sessions, "b", 2, None);
+        assert_eq!(sessions.registry.parked.lock().unwrap().len(), 2);
+        clock.advance(5_000);
+        sessions.sweep();
+        assert!(ended.lock().unwrap().is_empty());
+        let a = sessions.take("a", None).unwrap();
+        assert_eq!(a.mailbox, 1);
+        assert!(sessions.take("a", None).is_none());
+        // it still gets its messages until it is resumed
+        assert!(sessions.registry.parked.lock().unwrap().contains_key("a"));
+        sessions.resume(&a, 1, || {});
+        assert!(!sessions.registry.parked.lock().unwrap().contains_key("a"));
+        clock.advance(5_000);
+        assert!(sessions.take("b", None).is_none());
+        assert_eq!(*ended.lock().unwrap(), vec![2]);
+        assert!(sessions.registry.parked.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn only_the_same_identity_can_resume() {
+        let (sessions, _, ended) = sessions();
+        let ada = Identity::new("ada");
+        park(&sessions, "a", 1, Some(ada.clone()));
+        assert!(sessions.take("a", None).is_none());
+        assert!(sessions.take("a", Some(&Identity::new("bob"))).is_none());
+        assert!(sessions.take("a", Some(&ada)).is_some());
+        assert!(ended.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn full_buffers_end_the_session() {
+        let (sessions, _, ended) = sessions();
+        park(&sessions, "a", 1, None);
+        let link = sessions.registry.parked.lock().unwrap()["a"].link.clone();
+        assert!(link.send("x".repeat(1000)).is_ok());
+        assert!(matches!(link.send("x".repeat(100)), Err(Error::Closed)));
+        sessions.sweep();
+        assert_eq!(*ended.lock().unwrap(), vec![1]);
+        assert!(sessions.registry.parked.lock().unwrap().is_empty());
+        drop(sessions);
+        assert_eq!(*ended.lock().unwrap(), vec![1]);
+    }
+
+    #[test]
+    fn parked_sessions_end_with_the_server() {
+        let (sessions, _, ended) = sessions();
+        park(&sessions, "a", 1, None);
+        drop(sessions);
+        assert_eq!(*ended.lock().unwrap(), vec![1]);
+    }
+
+    #[test]
+    fn links_move_to_the_new_socket() {
+        let (_socket, first) = crate::sender::detached(Arc::default(), 3, 1);
+        let (_socket, second) = crate::sender::detached(Arc::default(), 5, 2);
+        let link = Link::new((*first).clone(), Arc::default(), None);
+        link.park(1024);
+        link.send("kept".to_string()).unwrap();
+        assert!(link.reopen((*second).clone()));
+        assert_eq!(link.socket(), (5, 2));
+        assert_eq!(link.first(), (3, 1));
+        assert_eq!(link.sender().token().0, 5);
+        // only a parked link can be reopened
+        assert!(!link.reopen((*first).clone()));
+    }
+
+    #[test]
+    fn send_to_only_reaches_a_parked_token_until_it_is_given_out_again() {
+        let (sessions, _, _) = sessions();
+        park(&sessions, "a", 1, None);
+        let (_socket, ctx) = crate::sender::detached(sessions.registry.clone(), 9, 9);
+        crate::send::send_to(&ctx, Token(0), JS, "1").unwrap();
+        sessions.open(0, || {});
+        assert!(matches!(
+            crate::send::send_to(&ctx, Token(0), JS, "2"),
+            Err(Error::UnknownToken(Token(0)))
+        ));
+    }
+
+    #[test]
+    fn session_ids_are_random() {
+        let (one, two) = (new_id().unwrap(), new_id().unwrap());
+        assert_eq!(one.len(), 32);
+        assert_ne!(one, two);
+    }
+}
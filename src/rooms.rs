@@ -0,0 +1,269 @@
+use razer_ws::util::Token;
+
+use crate::error::Result;
+use crate::event::event_type::Event;
+use crate::protocol::ServerMessage;
+use crate::send::{self, multicast_message, Targets};
+
+/// This will add the connection to a room, a room is made when the first connection joins it <br />
+/// every server has its own rooms, so a room with the same name on another `Listener` is a
+/// different room
+/// ```
+/// use razer::connections::ConnectionInfo;
+/// use razer::rooms::join;
+/// use razer::Sender;
+/// fn on_connect(info: ConnectionInfo, ctx: &Sender) {
+///     // one room for every document like `/doc?id=42`
+///     join(ctx, &info.resource);
+/// }
+/// ```
+/// a connection leaves all of its rooms when it is closed, so joining after that does nothing
+pub fn join(ctx: &crate::Sender, room: &str) {
+    let mut rooms = ctx.registry().rooms.lock().unwrap();
+    if crate::connections::closed(ctx) {
+        return;
+    }
+    let (token, connection_id) = ctx.socket();
+    rooms
+        .entry(room.to_string())
+        .or_default()
+        .insert(token, connection_id);
+}
+
+/// This will remove the connection from the room, this will return false if it was not in it <br />
+/// a room is removed when the last connection leaves
+pub fn leave(ctx: &crate::Sender, room: &str) -> bool {
+    let mut rooms = ctx.registry().rooms.lock().unwrap();
+    let members = match rooms.get_mut(room) {
+        Some(members) => members,
+        None => return false,
+    };
+    // the token may belong to a newer connection that joined the room itself
+    let (token, connection_id) = ctx.socket();
+    let removed = members.get(&token) == Some(&connection_id);
+    if removed {
+        members.remove(&token);
+    }
+    if members.is_empty() {
+        rooms.remove(room);
+    }
+    removed
+}
+
+/// This will remove the connection from every room it is in
+pub fn leave_all(ctx: &crate::Sender) {
+    let (token, connection_id) = ctx.socket();
+    ctx.registry().rooms.lock().unwrap().retain(|_, members| {
+        if members.get(&token) == Some(&connection_id) {
+            members.remove(&token);
+        }
+        !members.is_empty()
+    });
+}
+
+/// Every connection in the room, they can be used with `send_to`
+pub fn members(ctx: &crate::Sender, room: &str) -> Vec<Token> {
+    ctx.registry()
+        .rooms
+        .lock()
+        .unwrap()
+        .get(room)
+        .map(|members| members.keys().map(|token| Token::from(*token)).collect())
+        .unwrap_or_default()
+}
+
+/// This will return a bool on if the connection is in the room
+pub fn is_member(ctx: &crate::Sender, room: &str) -> bool {
+    let (token, connection_id) = ctx.socket();
+    ctx.registry()
+        .rooms
+        .lock()
+        .unwrap()
+        .get(room)
+        .is_some_and(|members| members.get(&token) == Some(&connection_id))
+}
+
+/// Every room the connection is in
+pub fn rooms_of(ctx: &crate::Sender) -> Vec<String> {
+    let (token, connection_id) = ctx.socket();
+    ctx.registry()
+        .rooms
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(_, members)| members.get(&token) == Some(&connection_id))
+        .map(|(room, _)| room.clone())
+        .collect()
+}
+
+/// Every room that has at least one connection
+pub fn rooms(ctx: &crate::Sender) -> Vec<String> {
+    ctx.registry()
+        .rooms
+        .lock()
+        .unwrap()
+        .keys()
+        .cloned()
+        .collect()
+}
+
+/// This will send data to every connection in the room, the connection that made the event does
+/// not have to be in it
+/// ```
+/// use razer::event::event_type::Event::JS;
+/// use razer::rooms::send_room;
+/// use razer::Sender;
+/// fn click(ctx: &Sender) {
+///     send_room(ctx, "lobby", JS, "alert(\"the game is starting\")").unwrap();
+/// }
+/// ```
+pub fn send_room(client: &crate::Sender, room: &str, event: Event, data: &str) -> Result<()> {
+    send_room_message(client, room, &ServerMessage::new(event, data))
+}
+
+/// The same as `send_room` but for any `ServerMessage`
+pub fn send_room_message(
+    client: &crate::Sender,
+    room: &str,
+    message: &ServerMessage,
+) -> Result<()> {
+    send_members(client, room, None, message)
+}
+
+/// The same as `send_room` but the connection that made the event is skipped
+/// ```
+/// use razer::dom::{escape_html, Command, Position};
+/// use razer::protocol::ServerMessage;
+/// use razer::rooms::send_room_message_except;
+/// use razer::Sender;
+/// fn typed(ctx: &Sender, line: &str) {
+///     // everyone else in the document sees the new line
+///     let message = ServerMessage::Dom {
+///         commands: vec![Command::insert("#doc", Position::BeforeEnd, &escape_html(line))],
+///     };
+///     send_room_message_except(ctx, "doc-42", &message).unwrap();
+/// }
+/// ```
+pub fn send_room_except(
+    client: &crate::Sender,
+    room: &str,
+    event: Event,
+    data: &str,
+) -> Result<()> {
+    send_room_message_except(client, room, &ServerMessage::new(event, data))
+}
+
+/// The same as `send_room_except` but for any `ServerMessage`
+pub fn send_room_message_except(
+    client: &crate::Sender,
+    room: &str,
+    message: &ServerMessage,
+) -> Result<()> {
+    send_members(client, room, Some(client.socket().0), message)
+}
+
+fn send_members(
+    client: &crate::Sender,
+    room: &str,
+    except: Option<usize>,
+    message: &ServerMessage,
+) -> Result<()> {
+    multicast_message(client, targets(client, room, except), message)
+}
+
+// every member of the room but `except`, with the parked sessions that were in it
+fn targets(client: &crate::Sender, room: &str, except: Option<usize>) -> Targets {
+    send::targets(
+        client,
+        |registry| match registry.rooms.lock().unwrap().get(room) {
+            Some(members) => members
+                .iter()
+                .filter(|(token, _)| Some(**token) != except)
+                .map(|(token, connection_id)| (Token::from(*token), *connection_id))
+                .collect(),
+            None => Vec::new(),
+        },
+        |away| away.rooms.iter().any(|joined| joined == room),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::event::event_type::Event::JS;
+    use crate::registry::Registry;
+
+    #[test]
+    fn connections_join_and_leave_rooms() {
+        let registry: Arc<Registry> = Arc::default();
+        registry
+            .connections
+            .lock()
+            .unwrap()
+            .extend([(1, 7), (2, 8)]);
+        let (_socket, one) = crate::sender::detached(registry.clone(), 1, 7);
+        let (_socket, two) = crate::sender::detached(registry.clone(), 2, 8);
+        join(&one, "lobby");
+        join(&two, "lobby");
+        join(&one, "game");
+        assert!(is_member(&one, "game"));
+        assert!(!is_member(&two, "game"));
+        let mut lobby = members(&one, "lobby");
+        lobby.sort_by_key(|token| token.0);
+        assert_eq!(lobby, vec![Token(1), Token(2)]);
+        let mut all = rooms_of(&one);
+        all.sort();
+        assert_eq!(all, vec!["game", "lobby"]);
+        assert!(leave(&one, "game"));
+        assert!(!leave(&one, "game"));
+        // the room is gone with its last member
+        assert_eq!(rooms(&one), vec!["lobby"]);
+        leave_all(&two);
+        assert_eq!(members(&one, "lobby"), vec![Token(1)]);
+    }
+
+    #[test]
+    fn closed_connections_can_not_join() {
+        let registry: Arc<Registry> = Arc::default();
+        let (_socket, ctx) = crate::sender::detached(registry.clone(), 1, 7);
+        join(&ctx, "lobby");
+        assert!(rooms(&ctx).is_empty());
+    }
+
+    #[test]
+    fn old_connections_do_not_leave_for_a_new_one_with_their_token() {
+        let registry: Arc<Registry> = Arc::default();
+        registry.connections.lock().unwrap().insert(1, 8);
+        let (_socket, old) = crate::sender::detached(registry.clone(), 1, 7);
+        let (_socket, new) = crate::sender::detached(registry.clone(), 1, 8);
+        join(&new, "lobby");
+        assert!(!leave(&old, "lobby"));
+        leave_all(&old);
+        assert_eq!(members(&new, "lobby"), vec![Token(1)]);
+        assert!(leave(&new, "lobby"));
+    }
+
+    #[test]
+    fn room_messages_only_go_to_members() {
+        let registry: Arc<Registry> = Arc::default();
+        registry
+            .connections
+            .lock()
+            .unwrap()
+            .extend([(1, 7), (2, 8), (3, 9)]);
+        let (_socket, one) = crate::sender::detached(registry.clone(), 1, 7);
+        let (_socket, two) = crate::sender::detached(registry.clone(), 2, 8);
+        join(&one, "lobby");
+        join(&two, "lobby");
+        let mut all = targets(&one, "lobby", None).open;
+        all.sort();
+        assert_eq!(all, vec![(Token(1), 7), (Token(2), 8)]);
+        assert_eq!(targets(&one, "lobby", Some(1)).open, vec![(Token(2), 8)]);
+        assert!(targets(&one, "game", None).open.is_empty());
+        send_room(&one, "lobby", JS, "1").unwrap();
+        send_room_except(&one, "lobby", JS, "1").unwrap();
+        send_room(&one, "game", JS, "1").unwrap();
+    }
+}
@@ -0,0 +1,355 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+
+use log::debug;
+use razer_ws::CloseCode;
+use serde_json::Value;
+
+use crate::connections::ConnectionInfo;
+use crate::error::Result;
+use crate::event::handler::EventHandler;
+use crate::event::subscription::subscribe;
+use crate::event::types::{BrowserEvent, Target};
+use crate::selector::Selector;
+
+type Handler = Arc<dyn Fn(Value, &crate::Sender) + Send + Sync>;
+
+#[derive(Clone)]
+struct Route {
+    id: RouteId,
+    selector: Option<Selector>,
+    handler: Handler,
+}
+
+type Routes = HashMap<String, Vec<Route>>;
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// The id of one closure that was mounted on a `Router`, it can be used to remove it again
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RouteId(u64);
+
+/// A way to handle events with closures instead of implementing `EventHandler`
+/// ```no_run
+/// use std::sync::atomic::{AtomicU32, Ordering};
+/// use std::sync::Arc;
+///
+/// use razer::event::event_type::Event::JS;
+/// use razer::event::types::{KeyboardEvent, MouseEvent};
+/// use razer::listener::Listener;
+/// use razer::router::Router;
+/// use razer::send::broadcast;
+/// use razer::Sender;
+///
+/// let clicks = Arc::new(AtomicU32::new(0));
+/// let counter = clicks.clone();
+///
+/// let router = Router::new()
+///     .on("click", move |_event: MouseEvent, ctx: &Sender| {
+///         let count = counter.fetch_add(1, Ordering::Relaxed) + 1;
+///         broadcast(ctx, JS, &format!("document.title = {}", count)).unwrap();
+///     })
+///     .on("keydown", |event: KeyboardEvent, _ctx: &Sender| println!("{}", event.key));
+///
+/// Listener::new().start(router).unwrap();
+/// ```
+/// the event can be any type from `razer::event::types` or a `Value` <br />
+/// cloning a `Router` is cheap and every clone shares the same routes, so you can keep a clone
+/// around to `mount` and `remove` closures while the server is running <br />
+/// the websites only send the events that have a closure, and they are told when an event is
+/// mounted or removed
+#[derive(Clone, Default)]
+pub struct Router {
+    routes: Arc<RwLock<Routes>>,
+    // every connection that uses the router, so they can be told when the events change, they
+    // are kept by `Sender::session` because a resumed session is disconnected from its new socket
+    clients: Arc<Mutex<HashMap<(usize, u32), crate::Sender>>>,
+}
+
+impl Router {
+    #[must_use]
+    pub fn new() -> Router {
+        Router::default()
+    }
+
+    /// Add a closure for the event and give back the router so calls can be chained
+    #[must_use]
+    pub fn on<E, F>(self, name: &str, handler: F) -> Router
+    where
+        E: BrowserEvent,
+        F: Fn(E, &crate::Sender) + Send + Sync + 'static,
+    {
+        self.mount(name, handler);
+        self
+    }
+
+    /// Add a closure that is only called when the event happened on an element that matches the
+    /// selector, or inside of one
+    /// ```no_run
+    /// use razer::event::types::{InputEvent, MouseEvent};
+    /// use razer::router::Router;
+    /// use razer::Sender;
+    ///
+    /// let router = Router::new()
+    ///     .on_selector("click", "#save", |_event: MouseEvent, _ctx: &Sender| println!("saving"))
+    ///     .on_selector("input", ".search-box", |event: InputEvent, _ctx: &Sender| {
+    ///         println!("searching for {:?}", event.target.value);
+    ///     });
+    /// ```
+    /// # Panics
+    /// this will panic if the selector is not valid, use `mount_selector` to get the error instead
+    #[must_use]
+    pub fn on_selector<E, F>(self, name: &str, selector: &str, handler: F) -> Router
+    where
+        E: BrowserEvent,
+        F: Fn(E, &crate::Sender) + Send + Sync + 'static,
+    {
+        if let Err(err) = self.mount_selector(name, selector, handler) {
+            panic!("{}", err);
+        }
+        self
+    }
+
+    /// Add a closure for the event, this can be done while the server is running
+    pub fn mount<E, F>(&self, name: &str, handler: F) -> RouteId
+    where
+        E: BrowserEvent,
+        F: Fn(E, &crate::Sender) + Send + Sync + 'static,
+    {
+        self.add(name, None, handler)
+    }
+
+    /// The same as `on_selector` but it can be done while the server is running
+    pub fn mount_selector<E, F>(&self, name: &str, selector: &str, handler: F) -> Result<RouteId>
+    where
+        E: BrowserEvent,
+        F: Fn(E, &crate::Sender) + Send + Sync + 'static,
+    {
+        let selector = Selector::parse(selector)?;
+        Ok(self.add(name, Some(selector), handler))
+    }
+
+    fn add<E, F>(&self, name: &str, selector: Option<Selector>, handler: F) -> RouteId
+    where
+        E: BrowserEvent,
+        F: Fn(E, &crate::Sender) + Send + Sync + 'static,
+    {
+        let id = RouteId(NEXT_ID.fetch_add(1, Ordering::Relaxed));
+        let new = {
+            let mut routes = self.routes.write().unwrap();
+            let new = !routes.contains_key(name);
+            routes.entry(name.to_string()).or_default().push(Route {
+                id,
+                selector,
+                handler: Arc::new(move |event, ctx| handler(E::from_value(event), ctx)),
+            });
+            new
+        };
+        if new {
+            self.resubscribe();
+        }
+        id
+    }
+
+    /// Remove a closure that was added with `mount` or `on`, this will return false if it was
+    /// already removed
+    pub fn remove(&self, id: RouteId) -> bool {
+        let mut removed = false;
+        let gone = {
+            let mut routes = self.routes.write().unwrap();
+            let before = routes.len();
+            routes.retain(|_, handlers| {
+                let before = handlers.len();
+                handlers.retain(|route| route.id != id);
+                removed |= handlers.len() != before;
+                !handlers.is_empty()
+            });
+            routes.len() != before
+        };
+        if gone {
+            self.resubscribe();
+        }
+        removed
+    }
+
+    /// Remove every closure for the event
+    pub fn clear(&self, name: &str) {
+        let gone = self.routes.write().unwrap().remove(name).is_some();
+        if gone {
+            self.resubscribe();
+        }
+    }
+
+    /// The names of every event that has at least one closure
+    pub fn events(&self) -> Vec<String> {
+        self.routes.read().unwrap().keys().cloned().collect()
+    }
+
+    // tell every open website which events the router has now
+    fn resubscribe(&self) {
+        let events = self.events();
+        let clients: Vec<crate::Sender> = self.clients.lock().unwrap().values().cloned().collect();
+        for client in clients {
+            if let Err(err) = subscribe(&client, &events) {
+                debug!("could not send the events to {:?}: {}", client.token(), err);
+            }
+        }
+    }
+
+    /// Call every closure for the event in the order they were added, closures with a selector
+    /// are only called if the target of the event matches <br />
+    /// this will return false if no closure was called
+    pub fn dispatch(&self, name: &str, event: Value, ctx: &crate::Sender) -> bool {
+        // the lock is not held while the closures run so they can mount and remove routes
+        let routes: Vec<Route> = match self.routes.read().unwrap().get(name) {
+            Some(routes) => routes.clone(),
+            None => return false,
+        };
+        let mut target = None;
+        let mut called = false;
+        for route in routes {
+            if let Some(selector) = &route.selector {
+                let target = target.get_or_insert_with(|| {
+                    serde_json::from_value::<Target>(event["target"].clone()).unwrap_or_default()
+                });
+                if !selector.matches(target) {
+                    continue;
+                }
+            }
+            (route.handler)(event.clone(), ctx);
+            called = true;
+        }
+        called
+    }
+}
+
+impl EventHandler for Router {
+    fn on_connect(&mut self, _info: ConnectionInfo, ctx: &crate::Sender) {
+        self.clients
+            .lock()
+            .unwrap()
+            .insert(ctx.session(), ctx.clone());
+    }
+
+    fn on_disconnect(&mut self, _code: CloseCode, _reason: &str, ctx: &crate::Sender) {
+        self.clients.lock().unwrap().remove(&ctx.session());
+    }
+
+    fn subscriptions(&self) -> Option<Vec<String>> {
+        Some(self.events())
+    }
+
+    fn on_event(&mut self, name: &str, event: Value, ctx: &crate::Sender) {
+        self.dispatch(name, event, ctx);
+    }
+}
+
+impl fmt::Debug for Router {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Router")
+            .field("events", &self.events())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::event::types::MouseEvent;
+    use crate::resume::Link;
+
+    type Log = Arc<Mutex<Vec<String>>>;
+
+    fn logger(log: &Log, name: &str) -> impl Fn(MouseEvent, &crate::Sender) {
+        let (log, name) = (log.clone(), name.to_string());
+        move |event, _ctx| {
+            log.lock()
+                .unwrap()
+                .push(format!("{} {}", name, event.client_x))
+        }
+    }
+
+    #[test]
+    fn closures_run_in_the_order_they_were_added() {
+        let (_socket, ctx) = crate::sender::detached(Arc::default(), 0, 0);
+        let log = Log::default();
+        let router = Router::new()
+            .on("click", logger(&log, "first"))
+            .on("click", logger(&log, "second"));
+        assert!(router.dispatch("click", json!({"clientX": 3}), &ctx));
+        assert!(!router.dispatch("keydown", json!({}), &ctx));
+        assert_eq!(*log.lock().unwrap(), vec!["first 3", "second 3"]);
+    }
+
+    #[test]
+    fn closures_can_be_removed_while_running() {
+        let (_socket, ctx) = crate::sender::detached(Arc::default(), 0, 0);
+        let log = Log::default();
+        let router = Router::new();
+        let first = router.mount("click", logger(&log, "first"));
+        router.mount("click", logger(&log, "second"));
+        router.mount("keydown", |_event: Value, _ctx: &crate::Sender| {});
+        let mut events = router.events();
+        events.sort();
+        assert_eq!(events, vec!["click", "keydown"]);
+        assert!(router.remove(first));
+        assert!(!router.remove(first));
+        router.clone().dispatch("click", json!({}), &ctx);
+        assert_eq!(*log.lock().unwrap(), vec!["second 0"]);
+        router.clear("click");
+        assert_eq!(router.events(), vec!["keydown"]);
+        assert!(!router.dispatch("click", json!({}), &ctx));
+    }
+
+    #[test]
+    fn selectors_only_match_their_target() {
+        let (_socket, ctx) = crate::sender::detached(Arc::default(), 0, 0);
+        let log = Log::default();
+        let router = Router::new()
+            .on_selector("click", "#save", logger(&log, "save"))
+            .on_selector("click", ".menu button", logger(&log, "menu"))
+            .on_selector("click", "[data-x=\"a,b\"]", logger(&log, "data"));
+        let click = |target| json!({"clientX": 1, "target": target});
+        router.dispatch("click", click(json!({"id": "save", "tag": "button"})), &ctx);
+        router.dispatch(
+            "click",
+            click(json!({"tag": "span", "parents": [{"tag": "button"}, {"classes": ["menu"]}]})),
+            &ctx,
+        );
+        router.dispatch(
+            "click",
+            click(json!({"tag": "a", "attributes": {"data-x": "a,b"}})),
+            &ctx,
+        );
+        // a click on the window has no target
+        router.dispatch("click", click(json!("Window")), &ctx);
+        assert_eq!(*log.lock().unwrap(), vec!["save 1", "menu 1", "data 1"]);
+        assert!(router
+            .mount_selector("click", "a ~ b", |_event: Value, _ctx: &crate::Sender| {})
+            .is_err());
+    }
+
+    #[test]
+    fn the_router_keeps_track_of_its_connections() {
+        let (_socket, ctx) = crate::sender::detached(Arc::default(), 3, 1);
+        let mut router = Router::new().on("click", |_event: Value, _ctx: &crate::Sender| {});
+        assert_eq!(router.subscriptions(), Some(vec!["click".to_string()]));
+        router.on_connect(ConnectionInfo::default(), &ctx);
+        assert_eq!(router.clients.lock().unwrap().len(), 1);
+        router.on_disconnect(CloseCode::Normal, "", &ctx);
+        assert!(router.clients.lock().unwrap().is_empty());
+
+        // a resumed session is disconnected with the sender of its new socket
+        let (_other, other) = crate::sender::detached(Arc::default(), 5, 2);
+        let link = Link::new((*ctx).clone(), Arc::default(), Some("id".to_string()));
+        router.on_connect(ConnectionInfo::default(), &link.sender());
+        link.park(1024);
+        assert!(link.reopen((*other).clone()));
+        router.on_disconnect(CloseCode::Normal, "", &link.sender());
+        assert!(router.clients.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,391 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::event::types::{Element, Target};
+
+/// A css selector that can be matched against the target of an event <br />
+/// it supports tags, `#id`, `.class`, `[attr]`, `[attr=value]`, `*`, the descendant (` `) and
+/// child (`>`) combinators and lists with `,`
+/// ```
+/// use razer::selector::Selector;
+/// let selector: Selector = "form.login > button#save, .search-box".parse().unwrap();
+/// ```
+/// a selector matches an event if the element the event happened on or one of its parents
+/// matches, so a click on a `<span>` inside of `<button id="save">` will match `#save`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selector {
+    source: String,
+    alternatives: Vec<Complex>,
+}
+
+/// Why a selector could not be parsed
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelectorError {
+    pub selector: String,
+    pub reason: String,
+}
+
+impl fmt::Display for SelectorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid selector {:?}: {}", self.selector, self.reason)
+    }
+}
+
+impl Error for SelectorError {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Combinator {
+    Descendant,
+    Child,
+}
+
+// the compounds are stored from the left most to the right most, every compound but the first
+// one has the combinator that joins it to the one before
+#[derive(Debug, Clone, PartialEq)]
+struct Complex {
+    compounds: Vec<(Combinator, Compound)>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Compound {
+    tag: Option<String>,
+    id: Option<String>,
+    classes: Vec<String>,
+    attributes: Vec<(String, Option<String>)>,
+}
+
+impl Selector {
+    pub fn parse(selector: &str) -> Result<Selector, SelectorError> {
+        let error = |reason: &str| SelectorError {
+            selector: selector.to_string(),
+            reason: reason.to_string(),
+        };
+        let mut alternatives = Vec::new();
+        for alternative in split_list(selector) {
+            alternatives.push(parse_complex(alternative).map_err(|reason| error(&reason))?);
+        }
+        Ok(Selector {
+            source: selector.to_string(),
+            alternatives,
+        })
+    }
+
+    /// The selector as it was written
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// This will check the target and all of its parents
+    pub fn matches(&self, target: &Target) -> bool {
+        let elements = target.elements();
+        (0..elements.len()).any(|start| self.matches_element(&elements[start..]))
+    }
+
+    /// This will only check the first element, the rest are its parents starting from the closest
+    pub fn matches_element(&self, elements: &[Element]) -> bool {
+        self.alternatives
+            .iter()
+            .any(|complex| matches_complex(&complex.compounds, elements))
+    }
+}
+
+impl FromStr for Selector {
+    type Err = SelectorError;
+
+    fn from_str(selector: &str) -> Result<Selector, SelectorError> {
+        Selector::parse(selector)
+    }
+}
+
+impl fmt::Display for Selector {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+// the selectors of a list, a `,` inside of quotes or `[]` like in `[data-x="a,b"]` is part of the
+// selector
+fn split_list(selector: &str) -> Vec<&str> {
+    let mut alternatives = Vec::new();
+    let mut quote = None;
+    let mut brackets = 0;
+    let mut start = 0;
+    for (i, c) in selector.char_indices() {
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '[') => brackets += 1,
+            (None, ']') => brackets -= 1,
+            (None, ',') if brackets <= 0 => {
+                alternatives.push(&selector[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    alternatives.push(&selector[start..]);
+    alternatives
+}
+
+fn parse_complex(selector: &str) -> Result<Complex, String> {
+    let mut compounds = Vec::new();
+    let mut combinator = Combinator::Descendant;
+    let mut chars = selector.trim().chars().peekable();
+    if chars.peek().is_none() {
+        return Err("empty selector".to_string());
+    }
+    while chars.peek().is_some() {
+        let mut compound = Compound::default();
+        let mut empty = true;
+        while let Some(&c) = chars.peek() {
+            match c {
+                '#' => {
+                    chars.next();
+                    compound.id = Some(parse_name(&mut chars)?);
+                }
+                '.' => {
+                    chars.next();
+                    compound.classes.push(parse_name(&mut chars)?);
+                }
+                '[' => {
+                    chars.next();
+                    compound.attributes.push(parse_attribute(&mut chars)?);
+                }
+                '*' if empty => {
+                    chars.next();
+                }
+                c if is_name(c) && empty => {
+                    compound.tag = Some(parse_name(&mut chars)?.to_lowercase());
+                }
+                _ => break,
+            }
+            empty = false;
+        }
+        if empty {
+            return Err(format!("unexpected {:?}", chars.peek().unwrap()));
+        }
+        compounds.push((combinator, compound));
+
+        let mut whitespace = false;
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+            whitespace = true;
+        }
+        combinator = match chars.peek() {
+            Some('>') => {
+                chars.next();
+                while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                    chars.next();
+                }
+                if chars.peek().is_none() {
+                    return Err("nothing after >".to_string());
+                }
+                Combinator::Child
+            }
+            Some(_) if whitespace => Combinator::Descendant,
+            Some(c) => return Err(format!("unexpected {:?}", c)),
+            None => break,
+        };
+    }
+    Ok(Complex { compounds })
+}
+
+fn is_name(c: char) -> bool {
+    c.is_alphanumeric() || c == '-' || c == '_' || !c.is_ascii()
+}
+
+fn parse_name(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<String, String> {
+    let mut name = String::new();
+    while let Some(&c) = chars.peek() {
+        if !is_name(c) {
+            break;
+        }
+        name.push(c);
+        chars.next();
+    }
+    if name.is_empty() {
+        return Err("expected a name".to_string());
+    }
+    Ok(name)
+}
+
+fn parse_attribute(
+    chars: &mut std::iter::Peekable<std::str::Chars>,
+) -> Result<(String, Option<String>), String> {
+    let name = parse_name(chars)?.to_lowercase();
+    match chars.next() {
+        Some(']') => Ok((name, None)),
+        Some('=') => {
+            let value = match chars.peek() {
+                Some(&quote) if quote == '"' || quote == '\'' => {
+                    chars.next();
+                    let mut value = String::new();
+                    loop {
+                        match chars.next() {
+                            Some(c) if c == quote => break,
+                            Some(c) => value.push(c),
+                            None => return Err("unclosed quote".to_string()),
+                        }
+                    }
+                    value
+                }
+                _ => parse_name(chars)?,
+            };
+            match chars.next() {
+                Some(']') => Ok((name, Some(value))),
+                _ => Err("expected ]".to_string()),
+            }
+        }
+        _ => Err("expected ] or =".to_string()),
+    }
+}
+
+fn matches_complex(compounds: &[(Combinator, Compound)], elements: &[Element]) -> bool {
+    let ((combinator, last), rest) = match compounds.split_last() {
+        Some(split) => split,
+        None => return true,
+    };
+    let element = match elements.first() {
+        Some(element) => element,
+        None => return false,
+    };
+    if !matches_compound(last, element) {
+        return false;
+    }
+    if rest.is_empty() {
+        return true;
+    }
+    match combinator {
+        Combinator::Child => matches_complex(rest, &elements[1..]),
+        Combinator::Descendant => {
+            (1..elements.len()).any(|parent| matches_complex(rest, &elements[parent..]))
+        }
+    }
+}
+
+fn matches_compound(compound: &Compound, element: &Element) -> bool {
+    compound
+        .tag
+        .as_ref()
+        .is_none_or(|tag| element.tag.eq_ignore_ascii_case(tag))
+        && compound.id.as_ref().is_none_or(|id| &element.id == id)
+        && compound
+            .classes
+            .iter()
+            .all(|class| element.classes.contains(class))
+        && compound.attributes.iter().all(|(name, value)| {
+            match (element.attributes.get(name), value) {
+                (Some(_), None) => true,
+                (Some(actual), Some(value)) => actual == value,
+                (None, _) => false,
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn element(tag: &str, id: &str, classes: &[&str]) -> Element {
+        Element {
+            id: id.to_string(),
+            tag: tag.to_string(),
+            classes: classes.iter().map(|class| class.to_string()).collect(),
+            ..Element::default()
+        }
+    }
+
+    // <form class="login"><button id="save" type="submit"><span>save</span></button></form>
+    fn target() -> Target {
+        let mut button = element("button", "save", &["big", "primary"]);
+        button
+            .attributes
+            .insert("type".to_string(), "submit".to_string());
+        Target {
+            tag: "span".to_string(),
+            parents: vec![
+                button,
+                element("form", "", &["login"]),
+                element("body", "", &[]),
+                element("html", "", &[]),
+            ],
+            ..Target::default()
+        }
+    }
+
+    fn matches(selector: &str) -> bool {
+        Selector::parse(selector).unwrap().matches(&target())
+    }
+
+    #[test]
+    fn simple_selectors_match_the_target_or_a_parent() {
+        assert!(matches("span"));
+        assert!(matches("#save"));
+        assert!(matches(".primary"));
+        assert!(matches("button.big.primary#save"));
+        assert!(matches("[type]"));
+        assert!(matches("[type=submit]"));
+        assert!(matches("[type=\"submit\"]"));
+        assert!(matches("*"));
+        assert!(!matches("#cancel"));
+        assert!(!matches(".small"));
+        assert!(!matches("button.small"));
+        assert!(!matches("[type=reset]"));
+        assert!(!matches("input"));
+    }
+
+    #[test]
+    fn combinators() {
+        assert!(matches("form #save"));
+        assert!(matches("form.login > button > span"));
+        assert!(matches("body span"));
+        assert!(matches("html form > button"));
+        assert!(!matches("form > span"));
+        assert!(!matches("button form"));
+        assert!(!matches(".search-box > span"));
+    }
+
+    #[test]
+    fn lists_match_any() {
+        assert!(matches(".search-box, #save"));
+        assert!(!matches(".search-box, #cancel"));
+    }
+
+    #[test]
+    fn commas_in_attribute_values_do_not_split_the_list() {
+        let mut target = target();
+        target
+            .attributes
+            .insert("data-x".to_string(), "a,b".to_string());
+        let selector = Selector::parse("[data-x=\"a,b\"], #cancel").unwrap();
+        assert_eq!(selector.alternatives.len(), 2);
+        assert!(selector.matches(&target));
+        assert!(Selector::parse("[data-x='a,b']").unwrap().matches(&target));
+        assert!(!Selector::parse("[data-x=\"a\"]").unwrap().matches(&target));
+        assert_eq!(
+            split_list("a, [x=\"],\"], b"),
+            vec!["a", " [x=\"],\"]", " b"]
+        );
+    }
+
+    #[test]
+    fn invalid_selectors() {
+        for selector in [
+            "",
+            " ",
+            "#",
+            ".",
+            "a >",
+            "a,",
+            "[type",
+            "[type=\"x]",
+            "[type=\"x,y]",
+            "a ~ b",
+            "a!",
+        ] {
+            assert!(Selector::parse(selector).is_err(), "{:?}", selector);
+        }
+    }
+}
@@ -1,73 +1,90 @@
-use std::thread;
+use std::sync::Arc;
 
 use razer_ws::util::Token;
 
+use crate::error::{Error, Result};
 use crate::event::event_type::Event;
-use crate::listener::CONNECTIONS;
+use crate::protocol::ServerMessage;
+use crate::resume::{Away, Link};
 
 /// This code will send data to the website
 /// ```
 /// use razer::event::event_type::Event::JS;
 /// use razer::send::send;
-/// use razer::{Sender, Value};
-/// fn load(&self, _event: Value, ctx: &Sender) {
+/// use razer::Sender;
+/// fn load(ctx: &Sender) {
 ///     if send(ctx, JS, "alert(\"Hello\")").is_err() {
 ///         println!("connection is closed");
-///     }   
+///     }
 /// }
 /// ```
-/// this will fail if the connection was closed
-pub fn send(client: &razer_ws::Sender, event: Event, data: &str) -> Result<(), ()> {
-    if crate::connections::closed(client) {
-        return Err(());
+/// this will fail with `Error::Closed` if the connection was closed, with `Listener::resume` the
+/// messages are kept for the website while it opens a new socket
+pub fn send(client: &crate::Sender, event: Event, data: &str) -> Result<()> {
+    send_message(client, &ServerMessage::new(event, data))
+}
+
+/// The same as `send` but for any `ServerMessage`
+pub fn send_message(client: &crate::Sender, message: &ServerMessage) -> Result<()> {
+    if let Some(link) = client.link() {
+        return link.send(message.encode());
     }
-    if client
-        .send(format!(
-            "{{\"name\":\"{}\", \"data\":\"{}\"}}",
-            event,
-            data.replace("\"", "\\\"")
-        ))
-        .is_ok()
-    {
-        return Ok(());
-    } else {
-        return Err(());
+    if crate::connections::closed(client) {
+        return Err(Error::Closed);
     }
+    Ok(client.send(message.encode())?)
 }
 
 /// This code will send data to a specific client that could be different than the one who made the event
 /// ```
 /// use razer::event::event_type::Event::JS;
 /// use razer::send::send_to;
-/// use razer::{Sender, Value};
+/// use razer::Sender;
 /// use razer::Token;
-/// fn load(&self, _event: Value, ctx: &Sender) {
+/// fn load(ctx: &Sender) {
 ///     if send_to(ctx, Token::from(0), JS, "alert(\"Hello\")").is_err() {
 ///         println!("connection is closed");
-///     }   
+///     }
 /// }
 /// ```
 /// this will send the alert to the first client that ever went onto the website <br />
-/// to get the current id you can use `ctx.token()`
-/// this will fail if the connection was closed
-pub fn send_to(
-    client: &razer_ws::Sender,
+/// to get the current id you can use `ctx.token()` <br />
+/// with `Listener::resume` a resumed session is on a new socket with a new token, but
+/// `ctx.token()` of a `Sender` that was kept from before still gives the old one, which only
+/// reaches the session while it is parked and no new connection was given that token <br />
+/// this will fail with `Error::UnknownToken` if no open connection has the token
+pub fn send_to(client: &crate::Sender, token: Token, event: Event, data: &str) -> Result<()> {
+    send_message_to(client, token, &ServerMessage::new(event, data))
+}
+
+/// The same as `send_to` but for any `ServerMessage`
+pub fn send_message_to(
+    client: &crate::Sender,
     token: Token,
-    event: Event,
-    data: &str,
-) -> Result<(), ()> {
-    let mut new_client = client.clone();
-    let connections = CONNECTIONS.lock().unwrap();
-    let connection = connections.clone();
-    drop(connections);
-    if connection.contains_key(&token.0) {
-        new_client.change_token(token, *connection.get(&token.0).unwrap());
-        if crate::connections::closed(&new_client) {
-            return Err(());
-        }
-        send(&new_client, event, data)
-    } else {
-        Err(())
+    message: &ServerMessage,
+) -> Result<()> {
+    let parked = client.registry().parked.lock().unwrap();
+    let connection_id = client
+        .registry()
+        .connections
+        .lock()
+        .unwrap()
+        .get(&token.0)
+        .copied();
+    if let Some(connection_id) = connection_id {
+        drop(parked);
+        return Ok(client.to(token, connection_id).send(message.encode())?);
+    }
+    // a session whose socket dropped keeps the message until its website is back, unless the
+    // token was given to a new connection since then
+    let link = parked
+        .values()
+        .find(|away| away.socket.is_some_and(|(parked, _)| parked == token.0))
+        .map(|away| away.link.clone());
+    drop(parked);
+    match link {
+        Some(link) => link.send(message.encode()),
+        None => Err(Error::UnknownToken(token)),
     }
 }
 
@@ -75,25 +92,74 @@ pub fn send_to(
 /// ```
 /// use razer::event::event_type::Event::JS;
 /// use razer::send::broadcast;
-/// use razer::{Sender, Value};
-/// fn load(&self, _event: Value, ctx: &Sender) {
-///     broadcast(ctx, JS, "alert(\"Hello\")");
+/// use razer::Sender;
+/// fn load(ctx: &Sender) {
+///     broadcast(ctx, JS, "alert(\"Hello\")").unwrap();
 /// }
 /// ```
-/// this will send the alert to every client that is currently on the site <br />
-pub fn broadcast(client: &razer_ws::Sender, event: Event, data: &str) {
-    let connections = CONNECTIONS.lock().unwrap();
-    let connection = connections.clone();
-    let mut threads = Vec::new();
-    drop(connections);
-    for (i, _) in connection.into_iter() {
-        let message = data.to_string();
-        let client = client.clone();
-        threads.push(thread::spawn(move || {
-            let _ = send_to(&client, Token::from(i.clone()), event.clone(), &message);
-        }));
+/// this will send the alert to every client that is currently on the site, a server only
+/// broadcasts to its own clients
+pub fn broadcast(client: &crate::Sender, event: Event, data: &str) -> Result<()> {
+    broadcast_message(client, &ServerMessage::new(event, data))
+}
+
+/// The same as `broadcast` but for any `ServerMessage`
+pub fn broadcast_message(client: &crate::Sender, message: &ServerMessage) -> Result<()> {
+    let targets = targets(
+        client,
+        |registry| {
+            registry
+                .connections
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(token, connection_id)| (Token::from(*token), *connection_id))
+                .collect()
+        },
+        |_| true,
+    );
+    multicast_message(client, targets, message)
+}
+
+/// Who a message to many connections goes to
+pub(crate) struct Targets {
+    pub(crate) open: Vec<(Token, u32)>,
+    // the sessions that wait for their website with `Listener::resume`
+    pub(crate) parked: Vec<Arc<Link>>,
+}
+
+// `open` reads the connections while the parked sessions are locked, so a session that is parked
+// or resumed at the same time is in one of them and gets the message once
+pub(crate) fn targets(
+    client: &crate::Sender,
+    open: impl FnOnce(&crate::registry::Registry) -> Vec<(Token, u32)>,
+    parked: impl Fn(&Away) -> bool,
+) -> Targets {
+    let away = client.registry().parked.lock().unwrap();
+    Targets {
+        open: open(client.registry()),
+        parked: away
+            .values()
+            .filter(|away| parked(away))
+            .map(|away| away.link.clone())
+            .collect(),
+    }
+}
+
+// the message is turned into json once and the event loop copies it to every connection, the
+// connections that closed while it was waiting are skipped
+pub(crate) fn multicast_message(
+    client: &crate::Sender,
+    targets: Targets,
+    message: &ServerMessage,
+) -> Result<()> {
+    let frame = message.encode();
+    for link in targets.parked {
+        // a session whose buffer is full can not be resumed anymore, the others still get it
+        let _ = link.send(frame.clone());
     }
-    for i in threads {
-        i.join().unwrap()
+    if !targets.open.is_empty() {
+        client.multicast(targets.open, frame)?;
     }
+    Ok(())
 }
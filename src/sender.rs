@@ -0,0 +1,111 @@
+use std::fmt;
+use std::ops::Deref;
+use std::sync::Arc;
+
+use razer_ws::util::Token;
+
+use crate::registry::Registry;
+use crate::resume::Link;
+
+/// The connection that an event came from, it is given to every event and can be used with the
+/// functions in `send`, `dom`, `rooms` and `eval` <br />
+/// it derefs to `razer_ws::Sender` so things like `ctx.token()` and `ctx.close(code)` work on it
+/// too <br />
+/// the sender only knows about the connections of its own server, so `send_to` and `broadcast`
+/// never reach the clients of another `Listener` in the same program
+#[derive(Clone)]
+pub struct Sender {
+    out: razer_ws::Sender,
+    registry: Arc<Registry>,
+    // the session this sender belongs to, messages go through it so they reach the website even
+    // after it opened a new socket
+    link: Option<Arc<Link>>,
+}
+
+impl Sender {
+    pub(crate) fn new(out: razer_ws::Sender, registry: Arc<Registry>) -> Sender {
+        Sender {
+            out,
+            registry,
+            link: None,
+        }
+    }
+
+    pub(crate) fn with_link(self, link: Arc<Link>) -> Sender {
+        Sender {
+            link: Some(link),
+            ..self
+        }
+    }
+
+    pub(crate) fn link(&self) -> Option<&Arc<Link>> {
+        self.link.as_ref()
+    }
+
+    /// The token and connection id the session started with, they stay the same when it is
+    /// resumed so they can be used to keep things for one session
+    pub(crate) fn session(&self) -> (usize, u32) {
+        match &self.link {
+            Some(link) => link.first(),
+            None => (self.out.token().0, self.out.connection_id()),
+        }
+    }
+
+    /// The token and connection id of the socket the messages go to now, this changes when the
+    /// session is resumed on a new socket
+    pub(crate) fn socket(&self) -> (usize, u32) {
+        match &self.link {
+            Some(link) => link.socket(),
+            None => (self.out.token().0, self.out.connection_id()),
+        }
+    }
+
+    pub(crate) fn registry(&self) -> &Arc<Registry> {
+        &self.registry
+    }
+
+    // a sender for another connection of the same server
+    pub(crate) fn to(&self, token: Token, connection_id: u32) -> Sender {
+        let mut out = self.out.clone();
+        out.change_token(token, connection_id);
+        Sender::new(out, self.registry.clone())
+    }
+}
+
+impl Deref for Sender {
+    type Target = razer_ws::Sender;
+
+    fn deref(&self) -> &razer_ws::Sender {
+        &self.out
+    }
+}
+
+impl fmt::Debug for Sender {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Sender")
+            .field("token", &self.out.token())
+            .field("connection_id", &self.out.connection_id())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+struct Nothing;
+
+#[cfg(test)]
+impl razer_ws::Handler for Nothing {}
+
+/// A sender for a connection of a server that never runs, for tests that do not need a socket
+/// <br />
+/// what it sends waits in the queue of the returned server, so keep that around while sending
+#[cfg(test)]
+pub(crate) fn detached(
+    registry: Arc<Registry>,
+    token: usize,
+    connection_id: u32,
+) -> (razer_ws::WebSocket<impl razer_ws::Factory>, Sender) {
+    let socket = razer_ws::WebSocket::new(|_| Nothing).unwrap();
+    let mut out = socket.broadcaster();
+    out.change_token(Token(token), connection_id);
+    (socket, Sender::new(out, registry))
+}
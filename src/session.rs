@@ -0,0 +1,143 @@
+use crate::event::handler::EventHandler;
+
+/// This builds a new session for every connection <br />
+/// the session is only ever used by that connection so it can keep its own state with `&mut self`
+/// and it gets dropped when the connection is closed
+/// ```no_run
+/// use razer::event::event_type::Event::JS;
+/// use razer::event::handler::EventHandler;
+/// use razer::event::types::MouseEvent;
+/// use razer::listener::Listener;
+/// use razer::send::send;
+/// use razer::session::SessionFactory;
+/// use razer::Sender;
+///
+/// pub struct Clicks(u32);
+///
+/// impl EventHandler for Clicks {
+///     fn click(&mut self, _event: MouseEvent, ctx: &Sender) {
+///         self.0 += 1;
+///         send(ctx, JS, &format!("alert(\"you clicked {} times\")", self.0)).unwrap();
+///     }
+/// }
+///
+/// pub struct Factory;
+///
+/// impl SessionFactory for Factory {
+///     type Session = Clicks;
+///
+///     fn create(&self) -> Clicks {
+///         Clicks(0)
+///     }
+/// }
+///
+/// Listener::new().start(Factory).unwrap();
+/// ```
+/// any `EventHandler` that is `Clone` is also a `SessionFactory`, every connection will get its own clone
+pub trait SessionFactory: Send + Sync + 'static {
+    type Session: EventHandler;
+
+    fn create(&self) -> Self::Session;
+}
+
+impl<H: EventHandler + Clone + Sync> SessionFactory for H {
+    type Session = H;
+
+    fn create(&self) -> H {
+        self.clone()
+    }
+}
+
+/// A `SessionFactory` made from a closure, you can make one with `from_fn`
+#[derive(Clone, Copy, Debug)]
+pub struct FnFactory<F>(F);
+
+impl<F, S> SessionFactory for FnFactory<F>
+where
+    F: Fn() -> S + Send + Sync + 'static,
+    S: EventHandler,
+{
+    type Session = S;
+
+    fn create(&self) -> S {
+        (self.0)()
+    }
+}
+
+/// This will make a `SessionFactory` that calls the closure for every new connection
+/// ```no_run
+/// use razer::listener::Listener;
+/// use razer::session::from_fn;
+/// # use razer::event::handler::EventHandler;
+/// # #[derive(Default)]
+/// # pub struct Clicks(u32);
+/// # impl EventHandler for Clicks {}
+///
+/// Listener::new().start(from_fn(Clicks::default)).unwrap();
+/// ```
+pub fn from_fn<F, S>(f: F) -> FnFactory<F>
+where
+    F: Fn() -> S + Send + Sync + 'static,
+    S: EventHandler,
+{
+    FnFactory(f)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::event::types::{GenericEvent, MouseEvent};
+
+    #[derive(Default)]
+    struct Clicks {
+        clicks: Vec<f64>,
+        unknown: Vec<String>,
+    }
+
+    impl EventHandler for Clicks {
+        fn click(&mut self, event: MouseEvent, _ctx: &crate::Sender) {
+            self.clicks.push(event.client_x);
+        }
+
+        fn on_unknown_event(&mut self, name: &str, _event: Value, _ctx: &crate::Sender) {
+            self.unknown.push(name.to_string());
+        }
+    }
+
+    #[test]
+    fn every_connection_gets_its_own_session() {
+        let (_socket, ctx) = crate::sender::detached(Arc::default(), 0, 0);
+        let factory = from_fn(Clicks::default);
+        let (mut first, mut second) = (factory.create(), factory.create());
+        first.on_event("click", json!({"clientX": 4}), &ctx);
+        first.on_event("click", json!({"clientX": 5}), &ctx);
+        second.on_event("cart:add", json!({}), &ctx);
+        assert_eq!(first.clicks, vec![4.0, 5.0]);
+        assert!(first.unknown.is_empty());
+        assert!(second.clicks.is_empty());
+        assert_eq!(second.unknown, vec!["cart:add".to_string()]);
+    }
+
+    #[test]
+    fn clone_handlers_are_factories() {
+        #[derive(Clone)]
+        struct Counter(u32);
+
+        impl EventHandler for Counter {
+            fn load(&mut self, _event: GenericEvent, _ctx: &crate::Sender) {
+                self.0 += 1;
+            }
+        }
+
+        let (_socket, ctx) = crate::sender::detached(Arc::default(), 0, 0);
+        let factory = Counter(10);
+        let mut session = factory.create();
+        session.on_event("load", Value::Null, &ctx);
+        assert_eq!(session.0, 11);
+        assert_eq!(factory.create().0, 10);
+    }
+}
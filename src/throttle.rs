@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+
+/// How often the events with one name reach the `EventHandler` of a connection, this is set with
+/// `Listener::policy` <br />
+/// the events are held back on the server before they are handled, so this works for events
+/// like `mousemove`, `scroll` or `timeupdate` that the website sends many times a second
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use razer::listener::Listener;
+/// use razer::throttle::Policy;
+/// # use razer::event::handler::EventHandler;
+/// # #[derive(Clone)]
+/// # pub struct Handler;
+/// # impl EventHandler for Handler {}
+///
+/// Listener::new()
+///     .policy("mousemove", Policy::per_second(30))
+///     .policy("input", Policy::Debounce(Duration::from_millis(300)))
+///     .policy("scroll", Policy::Latest)
+///     .start(Handler)
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Policy {
+    /// At most one event every `Duration`, the last event that was held back is handled at the
+    /// end of the wait so the handler always sees where things ended up
+    Throttle(Duration),
+    /// Only handle an event once none came in for `Duration`, the last one wins
+    Debounce(Duration),
+    /// Every event is handled, but if one with the same name is still waiting for the handler it
+    /// is replaced by the new one
+    Latest,
+}
+
+impl Policy {
+    /// `Policy::Throttle` that lets `events` through every second
+    pub fn per_second(events: u32) -> Policy {
+        Policy::Throttle(Duration::from_secs(1) / events.max(1))
+    }
+}
+
+pub(crate) type Policies = HashMap<String, Policy>;
+
+/// Where the time comes from, so the policies can be tested without waiting
+pub(crate) trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+}
+
+pub(crate) struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock that only moves when it is told to
+#[cfg(test)]
+pub(crate) struct FakeClock(std::sync::Mutex<Instant>);
+
+#[cfg(test)]
+impl FakeClock {
+    pub(crate) fn advance(&self, ms: u64) {
+        *self.0.lock().unwrap() += Duration::from_millis(ms);
+    }
+}
+
+#[cfg(test)]
+impl Default for FakeClock {
+    fn default() -> Self {
+        FakeClock(std::sync::Mutex::new(Instant::now()))
+    }
+}
+
+#[cfg(test)]
+impl Clock for FakeClock {
+    fn now(&self) -> Instant {
+        *self.0.lock().unwrap()
+    }
+}
+
+#[derive(Default)]
+struct Slot {
+    // when the last event was let through
+    last: Option<Instant>,
+    // the event that is held back and when it can be handled
+    held: Option<(Value, Instant)>,
+}
+
+/// The policies of one connection
+pub(crate) struct Throttler {
+    policies: Arc<Policies>,
+    clock: Arc<dyn Clock>,
+    slots: HashMap<String, Slot>,
+}
+
+impl Throttler {
+    pub(crate) fn new(policies: Arc<Policies>, clock: Arc<dyn Clock>) -> Throttler {
+        Throttler {
+            policies,
+            clock,
+            slots: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn policy(&self, name: &str) -> Option<Policy> {
+        self.policies.get(name).copied()
+    }
+
+    /// The event if it can be handled now, otherwise it is kept until `due` gives it back
+    pub(crate) fn pass(&mut self, name: &str, event: Value) -> Option<Value> {
+        let now = self.clock.now();
+        match self.policy(name) {
+            None | Some(Policy::Latest) => Some(event),
+            Some(Policy::Throttle(every)) => {
+                let slot = self.slots.entry(name.to_string()).or_default();
+                match slot.last {
+                    Some(last) if now < last + every || slot.held.is_some() => {
+                        slot.held = Some((event, last + every));
+                        None
+                    }
+                    _ => {
+                        slot.last = Some(now);
+                        Some(event)
+                    }
+                }
+            }
+            Some(Policy::Debounce(quiet)) => {
+                let slot = self.slots.entry(name.to_string()).or_default();
+                slot.held = Some((event, now + quiet));
+                None
+            }
+        }
+    }
+
+    /// The events that were held back and can be handled now
+    pub(crate) fn due(&mut self) -> Vec<(String, Value)> {
+        let now = self.clock.now();
+        let mut due = Vec::new();
+        for (name, slot) in &mut self.slots {
+            if slot.held.as_ref().is_some_and(|(_, at)| *at <= now) {
+                let (event, _) = slot.held.take().unwrap();
+                slot.last = Some(now);
+                due.push((name.clone(), event));
+            }
+        }
+        due
+    }
+
+    /// How long until `due` has something, if anything is held back
+    pub(crate) fn next(&self) -> Option<Duration> {
+        let now = self.clock.now();
+        self.slots
+            .values()
+            .filter_map(|slot| slot.held.as_ref().map(|(_, at)| *at))
+            .min()
+            .map(|at| at.saturating_duration_since(now))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn throttler(name: &str, policy: Policy) -> (Throttler, Arc<FakeClock>) {
+        let clock = Arc::new(FakeClock::default());
+        let policies = Arc::new(HashMap::from([(name.to_string(), policy)]));
+        (Throttler::new(policies, clock.clone()), clock)
+    }
+
+    #[test]
+    fn throttled_events_are_let_through_once_every_interval() {
+        let (mut throttler, clock) = throttler("mousemove", Policy::per_second(10));
+        assert_eq!(throttler.pass("mousemove", json!(0)), Some(json!(0)));
+        clock.advance(30);
+        assert_eq!(throttler.pass("mousemove", json!(1)), None);
+        clock.advance(30);
+        assert_eq!(throttler.pass("mousemove", json!(2)), None);
+        assert_eq!(throttler.next(), Some(Duration::from_millis(40)));
+        assert!(throttler.due().is_empty());
+        clock.advance(40);
+        // the last one that was held back is handled at the end of the interval
+        assert_eq!(throttler.due(), vec![("mousemove".to_string(), json!(2))]);
+        assert_eq!(throttler.next(), None);
+        clock.advance(50);
+        assert_eq!(throttler.pass("mousemove", json!(3)), None);
+        clock.advance(50);
+        assert_eq!(throttler.due(), vec![("mousemove".to_string(), json!(3))]);
+        clock.advance(100);
+        assert_eq!(throttler.pass("mousemove", json!(4)), Some(json!(4)));
+        // events without a policy are never held back
+        assert_eq!(throttler.pass("click", json!(5)), Some(json!(5)));
+    }
+
+    #[test]
+    fn debounced_events_wait_until_nothing_came_in() {
+        let (mut throttler, clock) =
+            throttler("input", Policy::Debounce(Duration::from_millis(300)));
+        assert_eq!(throttler.pass("input", json!("h")), None);
+        clock.advance(200);
+        assert_eq!(throttler.pass("input", json!("hi")), None);
+        clock.advance(200);
+        assert!(throttler.due().is_empty());
+        assert_eq!(throttler.next(), Some(Duration::from_millis(100)));
+        clock.advance(100);
+        assert_eq!(throttler.due(), vec![("input".to_string(), json!("hi"))]);
+        assert!(throttler.due().is_empty());
+    }
+
+    #[test]
+    fn latest_events_are_never_held_back() {
+        let (mut throttler, _) = throttler("scroll", Policy::Latest);
+        assert_eq!(throttler.pass("scroll", json!(1)), Some(json!(1)));
+        assert_eq!(throttler.pass("scroll", json!(2)), Some(json!(2)));
+        assert_eq!(throttler.next(), None);
+    }
+}
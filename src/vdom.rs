@@ -0,0 +1,482 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::dom::escape_html;
+use crate::error::Result;
+use crate::protocol::ServerMessage;
+use crate::send::send_message;
+
+/// A tree of html that razer can compare with the last one it sent
+/// ```
+/// use razer::vdom::Node;
+/// let list = Node::element("ul")
+///     .attr("id", "todo")
+///     .child(Node::element("li").child(Node::text("milk")))
+///     .child(Node::element("li").child(Node::text("eggs")));
+/// assert_eq!(list.to_html(), "<ul id=\"todo\"><li>milk</li><li>eggs</li></ul>");
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Node {
+    Element {
+        tag: String,
+        #[serde(default)]
+        attributes: BTreeMap<String, String>,
+        #[serde(default)]
+        children: Vec<Node>,
+    },
+    Text(String),
+}
+
+const VOID_TAGS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+impl Node {
+    pub fn element(tag: &str) -> Node {
+        Node::Element {
+            tag: tag.to_lowercase(),
+            attributes: BTreeMap::new(),
+            children: Vec::new(),
+        }
+    }
+
+    pub fn text(text: &str) -> Node {
+        Node::Text(text.to_string())
+    }
+
+    /// Set an attribute, this does nothing on a text node
+    #[must_use]
+    pub fn attr(mut self, name: &str, value: &str) -> Node {
+        if let Node::Element { attributes, .. } = &mut self {
+            attributes.insert(name.to_string(), value.to_string());
+        }
+        self
+    }
+
+    /// Add a child at the end, this does nothing on a text node
+    #[must_use]
+    pub fn child(mut self, child: Node) -> Node {
+        if let Node::Element { children, .. } = &mut self {
+            children.push(child);
+        }
+        self
+    }
+
+    /// Add all of the children at the end, this does nothing on a text node
+    #[must_use]
+    pub fn children<I: IntoIterator<Item = Node>>(mut self, new: I) -> Node {
+        if let Node::Element { children, .. } = &mut self {
+            children.extend(new);
+        }
+        self
+    }
+
+    /// Turn the tree into html, all the text and attributes are escaped <br />
+    /// a tag or attribute name has to look like `[A-Za-z][A-Za-z0-9-:_]*`, an attribute with any
+    /// other name is left out and so is an element with any other tag and its children
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        self.write_html(&mut html);
+        html
+    }
+
+    fn write_html(&self, html: &mut String) {
+        match self {
+            Node::Text(text) => html.push_str(&escape_html(text)),
+            Node::Element {
+                tag,
+                attributes,
+                children,
+            } => {
+                // names can not be escaped, so one like `x onload=alert(1)` would add markup
+                if !valid_name(tag) {
+                    return;
+                }
+                html.push('<');
+                html.push_str(tag);
+                for (name, value) in attributes.iter().filter(|(name, _)| valid_name(name)) {
+                    html.push(' ');
+                    html.push_str(name);
+                    html.push_str("=\"");
+                    html.push_str(&escape_html(value));
+                    html.push('"');
+                }
+                html.push('>');
+                if VOID_TAGS.contains(&tag.as_str()) {
+                    return;
+                }
+                for child in children {
+                    child.write_html(html);
+                }
+                html.push_str("</");
+                html.push_str(tag);
+                html.push('>');
+            }
+        }
+    }
+}
+
+fn valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | ':' | '_'))
+}
+
+/// One change to the website, the `path` is the index of every child to go through from the root
+/// of the view, so `[]` is the root itself and `[1, 0]` is the first child of its second child
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Patch {
+    /// Replace the node, if the path is empty the whole view is replaced
+    Replace {
+        path: Vec<usize>,
+        node: Node,
+    },
+    /// Add a child at the end of the node
+    Append {
+        path: Vec<usize>,
+        node: Node,
+    },
+    Remove {
+        path: Vec<usize>,
+    },
+    SetAttribute {
+        path: Vec<usize>,
+        name: String,
+        value: String,
+    },
+    RemoveAttribute {
+        path: Vec<usize>,
+        name: String,
+    },
+    SetText {
+        path: Vec<usize>,
+        text: String,
+    },
+}
+
+/// This will give the patches that turn `old` into `new` <br />
+/// children are compared by their position, so adding to or removing from the end of a list is
+/// cheap but adding to the front will change every child after it
+/// ```
+/// use razer::vdom::{diff, Node, Patch};
+/// let old = Node::element("p").child(Node::text("1 click"));
+/// let new = Node::element("p").child(Node::text("2 clicks"));
+/// assert_eq!(
+///     diff(&old, &new),
+///     vec![Patch::SetText { path: vec![0], text: "2 clicks".to_string() }]
+/// );
+/// ```
+pub fn diff(old: &Node, new: &Node) -> Vec<Patch> {
+    let mut patches = Vec::new();
+    diff_node(old, new, &mut Vec::new(), &mut patches);
+    patches
+}
+
+fn diff_node(old: &Node, new: &Node, path: &mut Vec<usize>, patches: &mut Vec<Patch>) {
+    match (old, new) {
+        (Node::Text(old), Node::Text(new)) => {
+            if old != new {
+                patches.push(Patch::SetText {
+                    path: path.clone(),
+                    text: new.clone(),
+                });
+            }
+        }
+        (
+            Node::Element {
+                tag: old_tag,
+                attributes: old_attributes,
+                children: old_children,
+            },
+            Node::Element {
+                tag: new_tag,
+                attributes: new_attributes,
+                children: new_children,
+            },
+        ) if old_tag == new_tag => {
+            for name in old_attributes.keys() {
+                if !new_attributes.contains_key(name) {
+                    patches.push(Patch::RemoveAttribute {
+                        path: path.clone(),
+                        name: name.clone(),
+                    });
+                }
+            }
+            for (name, value) in new_attributes {
+                if old_attributes.get(name) != Some(value) {
+                    patches.push(Patch::SetAttribute {
+                        path: path.clone(),
+                        name: name.clone(),
+                        value: value.clone(),
+                    });
+                }
+            }
+            for (i, (old, new)) in old_children.iter().zip(new_children).enumerate() {
+                path.push(i);
+                diff_node(old, new, path, patches);
+                path.pop();
+            }
+            for new in new_children.iter().skip(old_children.len()) {
+                patches.push(Patch::Append {
+                    path: path.clone(),
+                    node: new.clone(),
+                });
+            }
+            // removed from the back so the index of the children before does not change
+            for i in (new_children.len()..old_children.len()).rev() {
+                path.push(i);
+                patches.push(Patch::Remove { path: path.clone() });
+                path.pop();
+            }
+        }
+        _ => patches.push(Patch::Replace {
+            path: path.clone(),
+            node: new.clone(),
+        }),
+    }
+}
+
+/// Keeps the last tree that was sent to one connection and only sends what changed
+/// ```
+/// use razer::event::handler::EventHandler;
+/// use razer::event::types::MouseEvent;
+/// use razer::vdom::{LiveView, Node};
+/// use razer::Sender;
+///
+/// pub struct Counter {
+///     count: u32,
+///     view: LiveView,
+/// }
+///
+/// impl EventHandler for Counter {
+///     fn click(&mut self, _event: MouseEvent, ctx: &Sender) {
+///         self.count += 1;
+///         let tree = Node::element("p").child(Node::text(&format!("{} clicks", self.count)));
+///         self.view.render(ctx, tree).unwrap();
+///     }
+/// }
+/// ```
+/// the view is put inside of the first element that matches `root`, anything that was in there
+/// before is replaced on the first render
+#[derive(Debug, Clone)]
+pub struct LiveView {
+    root: String,
+    last: Option<Node>,
+}
+
+impl LiveView {
+    pub fn new(root: &str) -> LiveView {
+        LiveView {
+            root: root.to_string(),
+            last: None,
+        }
+    }
+
+    /// The selector of the element the view is put in
+    pub fn root(&self) -> &str {
+        &self.root
+    }
+
+    /// The last tree that was rendered
+    pub fn last(&self) -> Option<&Node> {
+        self.last.as_ref()
+    }
+
+    /// Forget the last tree so the next render sends the whole tree again
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+
+    /// This will remember the tree and give back the patches, without sending anything
+    pub fn update(&mut self, tree: Node) -> Vec<Patch> {
+        let patches = match &self.last {
+            Some(last) => diff(last, &tree),
+            None => vec![Patch::Replace {
+                path: Vec::new(),
+                node: tree.clone(),
+            }],
+        };
+        self.last = Some(tree);
+        patches
+    }
+
+    /// This will send the patches to the website, nothing is sent if the tree did not change <br />
+    /// this will fail with `Error::Closed` if the connection was closed
+    pub fn render(&mut self, client: &crate::Sender, tree: Node) -> Result<()> {
+        let patches = self.update(tree);
+        if patches.is_empty() {
+            return Ok(());
+        }
+        let sent = send_message(
+            client,
+            &ServerMessage::Patch {
+                root: self.root.clone(),
+                patches,
+            },
+        );
+        if sent.is_err() {
+            // the website does not have this tree so it has to be sent again
+            self.reset();
+        }
+        sent
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(items: &[&str]) -> Node {
+        Node::element("ul").children(
+            items
+                .iter()
+                .map(|item| Node::element("li").child(Node::text(item))),
+        )
+    }
+
+    #[test]
+    fn same_tree_has_no_patches() {
+        let tree = list(&["a", "b"]).attr("class", "list");
+        assert_eq!(diff(&tree, &tree.clone()), Vec::new());
+    }
+
+    #[test]
+    fn text_changes() {
+        assert_eq!(
+            diff(&list(&["a", "b"]), &list(&["a", "c"])),
+            vec![Patch::SetText {
+                path: vec![1, 0],
+                text: "c".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn attribute_changes() {
+        let old = Node::element("input")
+            .attr("type", "text")
+            .attr("value", "1");
+        let new = Node::element("input")
+            .attr("value", "2")
+            .attr("disabled", "");
+        assert_eq!(
+            diff(&old, &new),
+            vec![
+                Patch::RemoveAttribute {
+                    path: vec![],
+                    name: "type".to_string()
+                },
+                Patch::SetAttribute {
+                    path: vec![],
+                    name: "disabled".to_string(),
+                    value: String::new()
+                },
+                Patch::SetAttribute {
+                    path: vec![],
+                    name: "value".to_string(),
+                    value: "2".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn children_are_appended() {
+        assert_eq!(
+            diff(&list(&["a"]), &list(&["a", "b", "c"])),
+            vec![
+                Patch::Append {
+                    path: vec![],
+                    node: Node::element("li").child(Node::text("b"))
+                },
+                Patch::Append {
+                    path: vec![],
+                    node: Node::element("li").child(Node::text("c"))
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn children_are_removed_from_the_back() {
+        assert_eq!(
+            diff(&list(&["a", "b", "c"]), &list(&["a"])),
+            vec![
+                Patch::Remove { path: vec![2] },
+                Patch::Remove { path: vec![1] }
+            ]
+        );
+    }
+
+    #[test]
+    fn different_nodes_are_replaced() {
+        let old = Node::element("div").child(Node::element("p"));
+        let new = Node::element("div").child(Node::element("span"));
+        assert_eq!(
+            diff(&old, &new),
+            vec![Patch::Replace {
+                path: vec![0],
+                node: Node::element("span")
+            }]
+        );
+        assert_eq!(
+            diff(&Node::text("a"), &Node::element("b")),
+            vec![Patch::Replace {
+                path: vec![],
+                node: Node::element("b")
+            }]
+        );
+    }
+
+    #[test]
+    fn live_view_sends_the_whole_tree_first() {
+        let mut view = LiveView::new("#app");
+        assert_eq!(
+            view.update(list(&["a"])),
+            vec![Patch::Replace {
+                path: vec![],
+                node: list(&["a"])
+            }]
+        );
+        assert_eq!(view.update(list(&["a"])), Vec::new());
+        assert_eq!(
+            view.update(list(&[])),
+            vec![Patch::Remove { path: vec![0] }]
+        );
+        view.reset();
+        assert_eq!(view.update(list(&[])).len(), 1);
+    }
+
+    #[test]
+    fn html_is_escaped() {
+        let tree = Node::element("p")
+            .attr("title", "\"><script>")
+            .child(Node::text("<b>&</b>"))
+            .child(Node::element("br"));
+        assert_eq!(
+            tree.to_html(),
+            "<p title=\"&quot;&gt;&lt;script&gt;\">&lt;b&gt;&amp;&lt;/b&gt;<br></p>"
+        );
+    }
+
+    #[test]
+    fn names_that_would_add_markup_are_left_out() {
+        let tree = Node::element("p")
+            .attr("x onload=alert(1)", "")
+            .attr("data-ok", "1")
+            .attr("xlink:href", "#a")
+            .child(Node::element("img/src=x"))
+            .child(Node::element("script>alert(1)</script").child(Node::text("gone")))
+            .child(Node::text("kept"));
+        assert_eq!(
+            tree.to_html(),
+            "<p data-ok=\"1\" xlink:href=\"#a\">kept</p>"
+        );
+        assert_eq!(Node::element("1p").to_html(), "");
+        assert_eq!(Node::element("").to_html(), "");
+    }
+}